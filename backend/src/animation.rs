@@ -0,0 +1,115 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::PushResult;
+
+/// A tile moving from one cell to another during a push.
+/// Tiles that don't move are included too, with `from` equal to `to`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Slide {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+    pub value: u64,
+}
+
+impl Slide {
+    /// Position, as fractional row and column, after `progress` of the move, between 0 and 1
+    pub fn position(&self, progress: f64) -> (f64, f64) {
+        let progress = progress.clamp(0.0, 1.0);
+        let interpolate =
+            |from: usize, to: usize| from as f64 + (to as f64 - from as f64) * progress;
+        (
+            interpolate(self.from.0, self.to.0),
+            interpolate(self.from.1, self.to.1),
+        )
+    }
+}
+
+/// Every tile of `before`, the board preceding the push, with the cell it ends up in
+pub fn slides(before: &[Vec<u64>], result: &PushResult) -> Vec<Slide> {
+    let mut slides = vec![];
+    let mut moved = vec![vec![false; before[0].len()]; before.len()];
+    for (i, row) in result.transitions.iter().enumerate() {
+        for (j, pair) in row.iter().enumerate() {
+            for from in [pair.first, pair.second].into_iter().flatten() {
+                moved[from.0][from.1] = true;
+                slides.push(Slide {
+                    from,
+                    to: (i, j),
+                    value: before[from.0][from.1],
+                });
+            }
+        }
+    }
+    for (i, row) in before.iter().enumerate() {
+        for (j, &value) in row.iter().enumerate() {
+            if value != 0 && !moved[i][j] {
+                slides.push(Slide {
+                    from: (i, j),
+                    to: (i, j),
+                    value,
+                });
+            }
+        }
+    }
+    slides
+}
+
+/// Cells where two tiles merged during the push
+pub fn merges(result: &PushResult) -> Vec<(usize, usize)> {
+    let mut merges = vec![];
+    for (i, row) in result.transitions.iter().enumerate() {
+        for (j, pair) in row.iter().enumerate() {
+            if pair.len() == 2 {
+                merges.push((i, j));
+            }
+        }
+    }
+    merges
+}
+
+/// How fast the frontends animate moves
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationSpeed {
+    /// Move tiles instantly
+    Off,
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl AnimationSpeed {
+    pub const ALL: [AnimationSpeed; 4] = [
+        AnimationSpeed::Off,
+        AnimationSpeed::Slow,
+        AnimationSpeed::Normal,
+        AnimationSpeed::Fast,
+    ];
+
+    /// Time taken by tiles to slide to their new cell, zero when animations are off
+    pub fn slide_duration(self) -> Duration {
+        Duration::from_millis(match self {
+            AnimationSpeed::Off => 0,
+            AnimationSpeed::Slow => 200,
+            AnimationSpeed::Normal => 100,
+            AnimationSpeed::Fast => 50,
+        })
+    }
+}
+
+impl Display for AnimationSpeed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimationSpeed::Off => write!(f, "off"),
+            AnimationSpeed::Slow => write!(f, "slow"),
+            AnimationSpeed::Normal => write!(f, "normal"),
+            AnimationSpeed::Fast => write!(f, "fast"),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::AnimationSpeed, keymap::Keymap, palette::Theme, Game, DEFAULT_SIZE, MAX_SIZE,
+    MIN_SIZE,
+};
+
+/// File name of the configuration shared by the terminal frontends,
+/// and key under which the web frontend stores the same JSON
+pub const CONFIG_FILE: &str = "config.json";
+
+/// History length that keeps every move. [`Game`] never allocates its history upfront.
+/// Its value depends on the platform, so it's stored as `"unlimited"` in JSON
+pub const UNLIMITED_HISTORY: usize = usize::MAX;
+
+/// Preferences of the player, shared by every frontend.
+/// Missing fields take their default value
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Size of new games, clamped between [`MIN_SIZE`] and [`MAX_SIZE`]
+    pub height: usize,
+    pub width: usize,
+    /// Max history length of new games, 0 disables undo and [`UNLIMITED_HISTORY`] keeps every move
+    #[serde(with = "history_length")]
+    pub history: usize,
+    pub theme: Theme,
+    pub animation_speed: AnimationSpeed,
+    pub keymap: Keymap,
+    /// Distance in pixels a finger or pointer must travel to push, in the web frontend
+    pub swipe_threshold: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            height: DEFAULT_SIZE,
+            width: DEFAULT_SIZE,
+            history: 1,
+            theme: Theme::default(),
+            animation_speed: AnimationSpeed::default(),
+            keymap: Keymap::default(),
+            swipe_threshold: 30,
+        }
+    }
+}
+
+impl Config {
+    /// Start a game with the configured size and history, and a random seed if none is given
+    pub fn game(&self, seed: Option<u64>) -> Game {
+        let height = self.height.clamp(MIN_SIZE, MAX_SIZE);
+        let width = self.width.clamp(MIN_SIZE, MAX_SIZE);
+        match seed {
+            Some(seed) => Game::from_seed(height, width, self.history, seed),
+            None => Game::new(height, width, self.history),
+        }
+        .expect("sizes are clamped")
+    }
+}
+
+/// JSON form of history lengths: a number of moves, or `"unlimited"` for [`UNLIMITED_HISTORY`]
+pub(crate) mod history_length {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::UNLIMITED_HISTORY;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum HistoryLength {
+        Moves(u64),
+        Unlimited(Unlimited),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Unlimited {
+        Unlimited,
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        history: &usize,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if *history == UNLIMITED_HISTORY {
+            HistoryLength::Unlimited(Unlimited::Unlimited)
+        } else {
+            HistoryLength::Moves(*history as u64)
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<usize, D::Error> {
+        match HistoryLength::deserialize(deserializer)? {
+            HistoryLength::Moves(moves) => usize::try_from(moves).map_err(D::Error::custom),
+            HistoryLength::Unlimited(Unlimited::Unlimited) => Ok(UNLIMITED_HISTORY),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Game, DEFAULT_SIZE};
+
+/// File name used by the terminal frontends to store daily results
+pub const DAILY_FILE: &str = "daily.json";
+
+/// A calendar date in the proleptic Gregorian calendar
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// Create a date, returning `None` if it doesn't exist
+    pub fn new(year: i32, month: u8, day: u8) -> Option<Self> {
+        if (1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day) {
+            Some(Self { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// Current date in UTC, so that every player gets the same challenge at the same time
+    #[cfg(not(target_family = "wasm"))]
+    pub fn today() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_days((seconds / 86400) as i64)
+    }
+
+    /// Date `days` days after 1970-01-01
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let doe = days - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as i64;
+        Self {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+
+    /// Number of days since 1970-01-01
+    pub fn days(&self) -> i64 {
+        let month = self.month as i64;
+        let year = self.year as i64 - (month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let yoe = year - era * 400;
+        let doy = (153 * ((month + 9) % 12) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = ();
+
+    /// Parse a date in the `YYYY-MM-DD` format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or(());
+        let (year, month, day) = (next()?, next()?, next()?);
+        Self::new(
+            year.parse().map_err(|_| ())?,
+            month.parse().map_err(|_| ())?,
+            day.parse().map_err(|_| ())?,
+        )
+        .ok_or(())
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .parse()
+            .map_err(|_| format!("invalid date \"{}\"", value))
+    }
+}
+
+/// Seed and configuration of the challenge of a given day.
+/// Everyone playing on the same date gets the same board and the same spawns
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DailyChallenge {
+    pub date: Date,
+    pub seed: u64,
+    pub height: usize,
+    pub width: usize,
+    pub max_history: usize,
+}
+
+impl DailyChallenge {
+    pub fn new(date: Date) -> Self {
+        Self {
+            date,
+            seed: splitmix64(date.days() as u64),
+            height: DEFAULT_SIZE,
+            width: DEFAULT_SIZE,
+            max_history: 0,
+        }
+    }
+
+    pub fn game(&self) -> Game {
+        Game::from_seed(self.height, self.width, self.max_history, self.seed)
+            .expect("daily configuration must be valid")
+    }
+}
+
+/// Fixed, platform independent mixing function, so that seeds never change between versions
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub score: u64,
+    pub max_tile: u64,
+}
+
+/// Best result obtained in each daily challenge
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DailyResults(BTreeMap<Date, DailyResult>);
+
+impl DailyResults {
+    /// Record the result of `game` for `date`, keeping only the best score.
+    /// Returns `true` if the result is a new best for that date
+    pub fn record(&mut self, date: Date, game: &Game) -> bool {
+        let result = DailyResult {
+            score: game.score(),
+            max_tile: game.max_tile(),
+        };
+        match self.0.get(&date) {
+            Some(best) if best.score >= result.score => false,
+            _ => {
+                self.0.insert(date, result);
+                true
+            }
+        }
+    }
+
+    pub fn get(&self, date: Date) -> Option<DailyResult> {
+        self.0.get(&date).copied()
+    }
+
+    /// Results sorted by date, most recent first
+    pub fn iter(&self) -> impl Iterator<Item = (Date, DailyResult)> + '_ {
+        self.0.iter().rev().map(|(&date, &result)| (date, result))
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{daily::Date, Game, RULES};
+
+/// File name used by the terminal frontends to store high scores
+pub const HIGHSCORES_FILE: &str = "highscores.json";
+/// Number of scores kept for each board size
+pub const MAX_HIGHSCORES: usize = 10;
+
+/// A finished game. `seed` gives its first board, `moves` is only the number of moves played,
+/// so the game itself can't be replayed from it
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u64,
+    pub max_tile: u64,
+    pub moves: usize,
+    pub seed: u64,
+    pub date: Date,
+}
+
+/// Best scores grouped by board size and [`RULES`], each group sorted from the highest score
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores(BTreeMap<String, Vec<HighScore>>);
+
+impl HighScores {
+    /// Record `game`, played on `date`.
+    /// Returns its rank (starting from 0) if it entered the table
+    pub fn record(&mut self, game: &Game, date: Date) -> Option<usize> {
+        if game.score() == 0 {
+            return None;
+        }
+        let entry = HighScore {
+            score: game.score(),
+            max_tile: game.max_tile(),
+            moves: game.moves(),
+            seed: game.seed(),
+            date,
+        };
+        let scores = self.0.entry(key(game.height(), game.width())).or_default();
+        let rank = scores.partition_point(|s| s.score >= entry.score);
+        if rank >= MAX_HIGHSCORES {
+            return None;
+        }
+        scores.insert(rank, entry);
+        scores.truncate(MAX_HIGHSCORES);
+        Some(rank)
+    }
+
+    pub fn get(&self, height: usize, width: usize) -> &[HighScore] {
+        self.0
+            .get(&key(height, width))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+fn key(height: usize, width: usize) -> String {
+    format!("{}x{}/{}", height, width, RULES)
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Direction;
+
+/// Something the player can do with a key.
+/// Frontends ignore the actions they don't support
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Left,
+    Down,
+    Right,
+    Undo,
+    Save,
+    NewGame,
+    Daily,
+    Puzzles,
+    HighScores,
+    Stats,
+    Animations,
+    Settings,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::Up,
+        Action::Left,
+        Action::Down,
+        Action::Right,
+        Action::Undo,
+        Action::Save,
+        Action::NewGame,
+        Action::Daily,
+        Action::Puzzles,
+        Action::HighScores,
+        Action::Stats,
+        Action::Animations,
+        Action::Settings,
+        Action::Quit,
+    ];
+
+    /// Direction pushed by the action, if it is a move
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::Up => Some(Direction::U),
+            Action::Left => Some(Direction::L),
+            Action::Down => Some(Direction::D),
+            Action::Right => Some(Direction::R),
+            _ => None,
+        }
+    }
+}
+
+/// A key, written in the keymap file as the character itself,
+/// or `up`, `left`, `down` and `right` for the arrows
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Key {
+    Char(char),
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Up => write!(f, "up"),
+            Key::Left => write!(f, "left"),
+            Key::Down => write!(f, "down"),
+            Key::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Also accepts the browser names of the arrows, such as `ArrowUp`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        match lower.strip_prefix("arrow").unwrap_or(&lower) {
+            "up" => Ok(Key::Up),
+            "left" => Ok(Key::Left),
+            "down" => Ok(Key::Down),
+            "right" => Ok(Key::Right),
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Key::Char(c)),
+                    _ => Err(format!("unknown key \"{}\"", s)),
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+/// Built-in set of bindings that a keymap starts from
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    /// WASD and arrows to move, z to undo
+    #[default]
+    Default,
+    /// hjkl and arrows to move, u to undo
+    Vim,
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Preset::Default => write!(f, "default"),
+            Preset::Vim => write!(f, "vim"),
+        }
+    }
+}
+
+impl Preset {
+    pub const ALL: [Preset; 2] = [Preset::Default, Preset::Vim];
+
+    pub fn keys(self, action: Action) -> Vec<Key> {
+        let (up, left, down, right, undo) = match self {
+            Preset::Default => ('w', 'a', 's', 'd', 'z'),
+            Preset::Vim => ('k', 'h', 'j', 'l', 'u'),
+        };
+        match action {
+            Action::Up => vec![Key::Char(up), Key::Up],
+            Action::Left => vec![Key::Char(left), Key::Left],
+            Action::Down => vec![Key::Char(down), Key::Down],
+            Action::Right => vec![Key::Char(right), Key::Right],
+            Action::Undo => vec![Key::Char(undo)],
+            Action::Save => vec![Key::Char('v')],
+            Action::NewGame => vec![Key::Char('n')],
+            Action::Daily => vec![Key::Char('c')],
+            Action::Puzzles => vec![Key::Char('p')],
+            Action::HighScores => vec![Key::Char('r')],
+            Action::Stats => vec![Key::Char('t')],
+            Action::Animations => vec![Key::Char('m')],
+            Action::Settings => vec![Key::Char('o')],
+            Action::Quit => vec![Key::Char('q')],
+        }
+    }
+}
+
+/// Key bindings: a preset, with the keys of some actions replaced.
+/// Stored in the `keymap` field of the [configuration](crate::config::Config)
+///
+/// ```json
+/// { "preset": "vim", "bindings": { "undo": ["z", "u"] } }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+    pub preset: Preset,
+    /// Actions bound to other keys than in the preset
+    pub bindings: BTreeMap<Action, Vec<Key>>,
+}
+
+impl Keymap {
+    pub fn from_preset(preset: Preset) -> Self {
+        Self {
+            preset,
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// Keys bound to `action`
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .get(&action)
+            .cloned()
+            .unwrap_or_else(|| self.preset.keys(action))
+    }
+
+    /// Action bound to `key`. Remapped actions win over the preset
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(&action, _)| action)
+            .or_else(|| {
+                Action::ALL.into_iter().find(|action| {
+                    !self.bindings.contains_key(action) && self.preset.keys(*action).contains(&key)
+                })
+            })
+    }
+
+    /// Keys of `action` joined for display, such as `w/up`
+    pub fn describe(&self, action: Action) -> String {
+        self.keys(action)
+            .iter()
+            .map(Key::to_string)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Keys of the four moves, such as `wasd or arrows`
+    pub fn describe_moves(&self) -> String {
+        let moves = [
+            (Action::Up, Key::Up),
+            (Action::Left, Key::Left),
+            (Action::Down, Key::Down),
+            (Action::Right, Key::Right),
+        ];
+        let letters: Option<String> = moves
+            .iter()
+            .map(|&(action, _)| {
+                self.keys(action).into_iter().find_map(|key| match key {
+                    Key::Char(c) => Some(c),
+                    _ => None,
+                })
+            })
+            .collect();
+        let arrows = moves
+            .iter()
+            .all(|&(action, arrow)| self.keys(action).contains(&arrow));
+        match (letters, arrows) {
+            (Some(letters), true) => format!("{} or arrows", letters),
+            (Some(letters), false) => letters,
+            (None, _) => "arrows".to_string(),
+        }
+    }
+}
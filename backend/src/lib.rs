@@ -1,18 +1,38 @@
-use std::{collections::VecDeque, mem::swap};
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter},
+    mem::swap,
+};
 
-use rand::{rngs::StdRng, RngCore, SeedableRng};
-#[cfg(target_family = "wasm")]
-use serde::Serialize;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::pair::Pair;
+pub use crate::save::LoadError;
 
+pub mod animation;
+pub mod config;
+pub mod daily;
+pub mod highscores;
+pub mod keymap;
 mod pair;
+pub mod palette;
+pub mod puzzle;
+mod save;
+pub mod solver;
+pub mod stats;
+pub mod storage;
 #[cfg(test)]
 mod tests;
 
 pub const MIN_SIZE: usize = 3;
 pub const DEFAULT_SIZE: usize = 4;
 pub const MAX_SIZE: usize = 10;
+/// Version of the rules: tile spawns, merges and scoring.
+/// Bumped when a change makes the same seed and moves play out differently,
+/// so that scores and links from different rules are never mixed
+pub const RULES: u32 = 1;
 
 #[derive(Clone, Debug)]
 pub struct Game {
@@ -21,19 +41,43 @@ pub struct Game {
     transpose: Vec<Vec<u64>>,
     history: VecDeque<History>,
     max_history: usize,
-    rng: StdRng,
+    rng: ChaCha12Rng,
     seed: u64,
+    moves: usize,
+    merges: u64,
+    /// Unchanged since it was stored or loaded, see [`Game::is_suspended`]
+    suspended: bool,
+    /// Already counted by frontends, see [`Game::is_recorded`]
+    recorded: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct History {
     score: u64,
+    moves: usize,
+    merges: u64,
     board: Vec<Vec<u64>>,
-    rng: StdRng,
+    rng: ChaCha12Rng,
     direction: Direction,
+    /// Score gained by the move
+    gained: u64,
+    /// Row, column and value of the tile spawned after the move
+    spawned: (usize, usize, u64),
+}
+
+/// A move that can be undone, as listed by [`Game::history_moves`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Move {
+    /// Number of the move in the game, starting at 1
+    pub number: usize,
+    pub direction: Direction,
+    /// Score gained by merging tiles
+    pub gained: u64,
+    /// Row, column and value of the tile spawned after the move
+    pub spawned: (usize, usize, u64),
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     U,
     R,
@@ -55,8 +99,37 @@ impl TryFrom<char> for Direction {
     }
 }
 
+/// Reason why a board passed to [`Game::from_board`] was rejected
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BoardError {
+    /// Width or height is not between [`MIN_SIZE`] and [`MAX_SIZE`]
+    Size,
+    /// Rows have different lengths
+    NotRectangular,
+    /// A cell is neither empty nor a power of two greater than 1
+    Tile { row: usize, col: usize, value: u64 },
+}
+
+impl Display for BoardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::Size => write!(
+                f,
+                "width and height must be between {} and {}",
+                MIN_SIZE, MAX_SIZE
+            ),
+            BoardError::NotRectangular => write!(f, "all rows must have the same length"),
+            BoardError::Tile { row, col, value } => {
+                write!(f, "invalid tile {} at row {} column {}", value, row, col)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
-#[cfg_attr(target_family = "wasm", derive(Serialize))]
+#[derive(Serialize)]
 pub struct PushResult {
     pub transitions: Vec<Vec<Pair>>,
     pub spawned_row: usize,
@@ -87,8 +160,12 @@ impl Game {
                 transpose: vec![vec![0; height]; width],
                 history: VecDeque::new(),
                 max_history,
-                rng: StdRng::seed_from_u64(seed),
+                rng: ChaCha12Rng::seed_from_u64(seed),
                 seed,
+                moves: 0,
+                merges: 0,
+                suspended: false,
+                recorded: false,
             };
             result.spawn();
             result.spawn();
@@ -96,8 +173,35 @@ impl Game {
         }
     }
 
+    /// Create a game starting from the given board instead of two spawned tiles.
+    /// `seed` only drives the tiles spawned after each move.
+    ///
+    /// Every cell must be 0 or a power of two greater than 1,
+    /// and the board must respect the same size limits as [`Game::from_seed`]
+    pub fn from_board(
+        board: Vec<Vec<u64>>,
+        max_history: usize,
+        seed: u64,
+    ) -> Result<Self, BoardError> {
+        check_board(&board)?;
+        let (height, width) = (board.len(), board[0].len());
+        Ok(Self {
+            score: 0,
+            board,
+            transpose: vec![vec![0; height]; width],
+            history: VecDeque::new(),
+            max_history,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            seed,
+            moves: 0,
+            merges: 0,
+            suspended: false,
+            recorded: false,
+        })
+    }
+
     pub fn push(&mut self, direction: Direction) -> Option<PushResult> {
-        let before = History::new(self, self.rng.clone(), direction);
+        let mut before = History::new(self, self.rng.clone(), direction);
         let mut transitions = vec![vec![Pair::default(); self.width()]; self.height()];
         for (i, row) in self.board.iter_mut().enumerate() {
             for (j, cell) in row.iter_mut().enumerate() {
@@ -144,8 +248,12 @@ impl Game {
             }
         }
         if moved {
+            self.moves += 1;
+            self.suspended = false;
             let (spawned_row, spawned_col, spawned_value) = self.spawn();
             if self.max_history > 0 {
+                before.gained = self.score - before.score;
+                before.spawned = (spawned_row, spawned_col, spawned_value);
                 self.add_to_history(before);
             }
             Some(PushResult {
@@ -185,6 +293,7 @@ impl Game {
                 if row[j] != 0 && row[j] == row[j + 1] {
                     row[j] *= 2;
                     self.score += row[j];
+                    self.merges += 1;
                     row[j + 1] = 0;
                     while let Some(value) = transitions[i][j + 1].pop() {
                         transitions[i][j].push(value);
@@ -211,10 +320,29 @@ impl Game {
             self.board = history.board;
             self.score = history.score;
             self.rng = history.rng;
+            self.moves = history.moves;
+            self.merges = history.merges;
+            self.suspended = false;
             true
         }
     }
 
+    /// Undo moves until only `moves` of them are played.
+    /// Returns `false`, without undoing anything, if the history doesn't go back that far
+    pub fn undo_to(&mut self, moves: usize) -> bool {
+        let oldest = self
+            .history
+            .back()
+            .map_or(self.moves, |history| history.moves);
+        if moves < oldest || moves > self.moves {
+            return false;
+        }
+        while self.moves > moves {
+            self.undo();
+        }
+        true
+    }
+
     fn reverse(&mut self, transitions: &mut [Vec<Pair>]) {
         reverse(&mut self.board);
         reverse(transitions);
@@ -234,6 +362,7 @@ impl Game {
         }
         swap(&mut self.board, &mut self.transpose);
         let old = transitions.clone();
+        *transitions = vec![vec![Pair::default(); old.len()]; old[0].len()];
         for (i, row) in old.iter().enumerate() {
             for (j, pair) in row.iter().enumerate() {
                 transitions[j][i] = Pair {
@@ -255,7 +384,11 @@ impl Game {
                 self.rng.next_u32() as usize % self.board[0].len(),
             );
         }
-        let value = if self.rng.next_u32() % 10 == 0 { 4 } else { 2 };
+        let value = if self.rng.next_u32().is_multiple_of(10) {
+            4
+        } else {
+            2
+        };
         self.board[i][j] = value;
         (i, j, value)
     }
@@ -276,6 +409,12 @@ impl Game {
         self.max_history
     }
 
+    /// Change how many moves can be undone, forgetting the oldest ones if needed
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.max_history = max_history;
+        self.history.truncate(max_history);
+    }
+
     pub fn board(&self) -> &Vec<Vec<u64>> {
         &self.board
     }
@@ -284,13 +423,89 @@ impl Game {
         self.history.iter().rev().map(|h| h.direction).collect()
     }
 
+    /// Moves that can be undone, oldest first
+    pub fn history_moves(&self) -> Vec<Move> {
+        self.history
+            .iter()
+            .rev()
+            .map(|h| Move {
+                number: h.moves + 1,
+                direction: h.direction,
+                gained: h.gained,
+                spawned: h.spawned,
+            })
+            .collect()
+    }
+
     pub fn get(&self, i: usize, j: usize) -> u64 {
         self.board[i][j]
     }
 
+    pub fn max_tile(&self) -> u64 {
+        self.board.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// Whether no move is possible: the board is full and no two adjacent tiles are equal
+    pub fn is_over(&self) -> bool {
+        self.board.iter().enumerate().all(|(i, row)| {
+            row.iter().enumerate().all(|(j, &cell)| {
+                cell != 0
+                    && row.get(j + 1) != Some(&cell)
+                    && self.board.get(i + 1).map(|next| next[j]) != Some(cell)
+            })
+        })
+    }
+
     pub fn seed(&self) -> u64 {
         self.seed
     }
+
+    /// Number of moves played, not counting undone ones
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Number of merges performed, not counting undone ones
+    pub fn merges(&self) -> u64 {
+        self.merges
+    }
+
+    /// Whether the game is unchanged since it was last stored or loaded.
+    /// Leaving such a game only suspends it: frontends record it when it is left after a change
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Mark the game as suspended, once what [`Game::save`] returned is stored
+    pub fn suspend(&mut self) {
+        self.suspended = true;
+    }
+
+    /// Whether the game was loaded from a save marked by [`Game::mark_save_recorded`]:
+    /// a later state of it is already in the statistics and high scores,
+    /// so frontends don't record it again
+    pub fn is_recorded(&self) -> bool {
+        self.recorded
+    }
+}
+
+fn check_board(board: &[Vec<u64>]) -> Result<(), BoardError> {
+    let height = board.len();
+    let width = board.first().map_or(0, Vec::len);
+    if !(MIN_SIZE..=MAX_SIZE).contains(&width) || !(MIN_SIZE..=MAX_SIZE).contains(&height) {
+        return Err(BoardError::Size);
+    }
+    if board.iter().any(|row| row.len() != width) {
+        return Err(BoardError::NotRectangular);
+    }
+    for (row, cells) in board.iter().enumerate() {
+        for (col, &value) in cells.iter().enumerate() {
+            if value == 1 || (value != 0 && !value.is_power_of_two()) {
+                return Err(BoardError::Tile { row, col, value });
+            }
+        }
+    }
+    Ok(())
 }
 
 fn reverse<T>(matrix: &mut [Vec<T>]) {
@@ -304,12 +519,16 @@ impl Default for Game {
 }
 
 impl History {
-    fn new(game: &Game, rng: StdRng, direction: Direction) -> Self {
+    fn new(game: &Game, rng: ChaCha12Rng, direction: Direction) -> Self {
         Self {
             score: game.score,
+            moves: game.moves,
+            merges: game.merges,
             board: game.board.clone(),
             rng,
             direction,
+            gained: 0,
+            spawned: (0, 0, 0),
         }
     }
 }
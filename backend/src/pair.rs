@@ -1,7 +1,6 @@
 #[cfg(test)]
 use std::fmt::Debug;
 
-#[cfg(target_family = "wasm")]
 use serde::{Serialize, Serializer};
 
 type Coord = (usize, usize);
@@ -89,7 +88,6 @@ impl Debug for Pair {
     }
 }
 
-#[cfg(target_family = "wasm")]
 impl Serialize for Pair {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -0,0 +1,242 @@
+use std::fmt::{Display, Formatter};
+#[cfg(not(target_family = "wasm"))]
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_family = "wasm"))]
+use crate::storage;
+
+/// Directory of the configuration holding custom themes, one `<name>.json` [`Palette`] each
+pub const THEMES_DIR: &str = "themes";
+
+/// Color theme of the tiles. Frontends without themes always use the classic colors
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// The colors of the original game
+    #[default]
+    Classic,
+    /// Dark tiles on a dark background
+    Dark,
+    /// Bright tiles with black text on black
+    HighContrast,
+    /// Colors that stay distinct with the common kinds of color blindness
+    Colorblind,
+    /// Black, white and greys only, fine on 16 color terminals
+    Monochrome,
+    /// Palette loaded from [`THEMES_DIR`]
+    Custom(String),
+}
+
+impl Theme {
+    pub const BUILTIN: [Theme; 5] = [
+        Theme::Classic,
+        Theme::Dark,
+        Theme::HighContrast,
+        Theme::Colorblind,
+        Theme::Monochrome,
+    ];
+
+    /// Colors of a built-in theme, `None` for custom ones
+    pub fn builtin_palette(&self) -> Option<Palette> {
+        let palette = match self {
+            Theme::Classic => Palette {
+                background: (250, 248, 239),
+                text: DARK_TEXT,
+                empty: (204, 192, 179),
+                tiles: vec![
+                    (238, 228, 218),
+                    (237, 224, 200),
+                    (242, 177, 121),
+                    (245, 149, 99),
+                    (246, 124, 95),
+                    (246, 94, 59),
+                    (237, 207, 114),
+                    (237, 204, 97),
+                    (237, 200, 80),
+                    (237, 197, 63),
+                    (237, 194, 46),
+                    (60, 58, 50),
+                ],
+                dark_tiles: 2,
+                dark_text: DARK_TEXT,
+                light_text: LIGHT_TEXT,
+            },
+            Theme::Dark => Palette {
+                background: (30, 30, 30),
+                text: (220, 220, 220),
+                empty: (60, 58, 50),
+                tiles: vec![
+                    (90, 84, 74),
+                    (110, 100, 80),
+                    (170, 100, 50),
+                    (180, 80, 40),
+                    (185, 65, 45),
+                    (190, 45, 20),
+                    (175, 145, 50),
+                    (180, 140, 30),
+                    (185, 135, 20),
+                    (190, 130, 10),
+                    (200, 125, 0),
+                    (120, 40, 140),
+                ],
+                dark_tiles: 0,
+                dark_text: (30, 30, 30),
+                light_text: (240, 240, 240),
+            },
+            Theme::HighContrast => Palette {
+                background: (0, 0, 0),
+                text: (255, 255, 255),
+                empty: (0, 0, 0),
+                tiles: vec![
+                    (255, 255, 255),
+                    (255, 255, 0),
+                    (0, 255, 255),
+                    (0, 255, 0),
+                    (255, 128, 255),
+                    (255, 160, 0),
+                    (128, 192, 255),
+                    (255, 128, 128),
+                    (192, 255, 128),
+                    (255, 220, 128),
+                    (200, 200, 255),
+                ],
+                dark_tiles: usize::MAX,
+                dark_text: (0, 0, 0),
+                light_text: (255, 255, 255),
+            },
+            // Okabe-Ito colors, light ones first
+            Theme::Colorblind => Palette {
+                background: (255, 255, 255),
+                text: (0, 0, 0),
+                empty: (220, 220, 220),
+                tiles: vec![
+                    (240, 228, 66),
+                    (230, 159, 0),
+                    (86, 180, 233),
+                    (0, 158, 115),
+                    (0, 114, 178),
+                    (213, 94, 0),
+                    (204, 121, 167),
+                    (0, 0, 0),
+                ],
+                dark_tiles: 3,
+                dark_text: (0, 0, 0),
+                light_text: (255, 255, 255),
+            },
+            Theme::Monochrome => Palette {
+                background: (0, 0, 0),
+                text: (255, 255, 255),
+                empty: (0, 0, 0),
+                tiles: vec![(128, 128, 128), (192, 192, 192), (255, 255, 255)],
+                dark_tiles: usize::MAX,
+                dark_text: (0, 0, 0),
+                light_text: (255, 255, 255),
+            },
+            Theme::Custom(_) => return None,
+        };
+        Some(palette)
+    }
+
+    /// Colors of the theme, reading the file of custom themes
+    #[cfg(not(target_family = "wasm"))]
+    pub fn palette(&self) -> io::Result<Palette> {
+        match self {
+            Theme::Custom(name) => {
+                let path = storage::config_file(THEMES_DIR)
+                    .ok_or(io::ErrorKind::NotFound)?
+                    .join(format!("{}.json", name));
+                serde_json::from_str(&fs::read_to_string(path)?).map_err(io::Error::from)
+            }
+            builtin => Ok(builtin
+                .builtin_palette()
+                .expect("only custom themes have no built-in palette")),
+        }
+    }
+
+    /// Built-in themes, then the custom ones found in [`THEMES_DIR`]
+    #[cfg(not(target_family = "wasm"))]
+    pub fn all() -> Vec<Theme> {
+        let mut themes = Theme::BUILTIN.to_vec();
+        let Some(entries) = storage::config_file(THEMES_DIR).and_then(|dir| fs::read_dir(dir).ok())
+        else {
+            return themes;
+        };
+        let mut custom: Vec<_> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(Theme::Custom(
+                    path.file_stem()?.to_string_lossy().into_owned(),
+                ))
+            })
+            .collect();
+        custom.sort_by_key(Theme::to_string);
+        themes.extend(custom);
+        themes
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Theme::Classic => write!(f, "classic"),
+            Theme::Dark => write!(f, "dark"),
+            Theme::HighContrast => write!(f, "high contrast"),
+            Theme::Colorblind => write!(f, "colorblind safe"),
+            Theme::Monochrome => write!(f, "monochrome"),
+            Theme::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Colors of a theme
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    /// Behind the board
+    pub background: Rgb,
+    /// Text outside of tiles
+    pub text: Rgb,
+    pub empty: Rgb,
+    /// Tile backgrounds, starting at 2.
+    /// The last one is the super tile color, used by every bigger tile as well
+    pub tiles: Vec<Rgb>,
+    /// How many of the first tile colors are light enough for [`Palette::dark_text`]
+    pub dark_tiles: usize,
+    pub dark_text: Rgb,
+    pub light_text: Rgb,
+}
+
+impl Palette {
+    /// Index in `tiles` of the color of `value`, `None` for empty cells
+    fn index(&self, value: u64) -> Option<usize> {
+        let log = value.checked_ilog2().filter(|&log| log > 0)? as usize;
+        Some((log - 1).min(self.tiles.len().saturating_sub(1)))
+    }
+
+    /// Background of the tile with the given value, for any value
+    pub fn background(&self, value: u64) -> Rgb {
+        self.index(value)
+            .and_then(|index| self.tiles.get(index).copied())
+            .unwrap_or(self.empty)
+    }
+
+    /// Text color that stays readable over [`Palette::background`]
+    pub fn foreground(&self, value: u64) -> Rgb {
+        match self.index(value) {
+            Some(index) if index >= self.dark_tiles => self.light_text,
+            _ => self.dark_text,
+        }
+    }
+}
+
+/// A color as red, green and blue components
+pub type Rgb = (u8, u8, u8);
+
+/// Text color for dark backgrounds
+pub const LIGHT_TEXT: Rgb = (249, 246, 242);
+/// Text color for light backgrounds
+pub const DARK_TEXT: Rgb = (119, 110, 101);
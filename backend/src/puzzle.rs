@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BoardError, Game};
+
+/// Pack shipped with the game, in the same format accepted by [`PuzzlePack::from_json`]
+pub const BUILTIN_PACK: &str = include_str!("../puzzles/classic.json");
+
+/// Condition a puzzle must meet to be solved
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    /// Tile that must appear on the board
+    pub tile: u64,
+    /// Maximum number of moves allowed, `None` for no limit
+    #[serde(default)]
+    pub max_moves: Option<usize>,
+}
+
+impl Goal {
+    pub fn is_reached(&self, game: &Game) -> bool {
+        game.max_tile() >= self.tile
+    }
+
+    pub fn status(&self, game: &Game) -> PuzzleStatus {
+        if self.is_reached(game) {
+            PuzzleStatus::Solved
+        } else if game.is_over() || self.max_moves.is_some_and(|max| game.moves() >= max) {
+            PuzzleStatus::Failed
+        } else {
+            PuzzleStatus::InProgress
+        }
+    }
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Make a {}", self.tile)?;
+        match self.max_moves {
+            Some(1) => write!(f, " in 1 move"),
+            Some(max_moves) => write!(f, " in at most {} moves", max_moves),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PuzzleStatus {
+    InProgress,
+    Solved,
+    Failed,
+}
+
+/// A starting board with the seed used for spawns and a goal to reach
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub name: String,
+    pub board: Vec<Vec<u64>>,
+    #[serde(default)]
+    pub seed: u64,
+    pub goal: Goal,
+}
+
+impl Puzzle {
+    pub fn game(&self, max_history: usize) -> Result<Game, BoardError> {
+        Game::from_board(self.board.clone(), max_history, self.seed)
+    }
+}
+
+/// A named collection of puzzles, stored as JSON:
+///
+/// ```json
+/// {
+///     "name": "Example",
+///     "puzzles": [
+///         {
+///             "name": "First merge",
+///             "seed": 0,
+///             "goal": { "tile": 4, "max_moves": 1 },
+///             "board": [[2, 2, 0], [0, 0, 0], [0, 0, 0]]
+///         }
+///     ]
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl PuzzlePack {
+    /// Parse a pack, checking that every board and goal is valid
+    pub fn from_json(json: &str) -> Result<Self, PuzzleError> {
+        let pack: Self =
+            serde_json::from_str(json).map_err(|e| PuzzleError::Format(e.to_string()))?;
+        for puzzle in &pack.puzzles {
+            let tile = puzzle.goal.tile;
+            if tile < 4 || !tile.is_power_of_two() {
+                return Err(PuzzleError::Goal(puzzle.name.clone(), tile));
+            }
+            puzzle
+                .game(0)
+                .map_err(|e| PuzzleError::Board(puzzle.name.clone(), e))?;
+        }
+        Ok(pack)
+    }
+
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_PACK).expect("builtin pack must be valid")
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PuzzleError {
+    /// The file is not a valid pack
+    Format(String),
+    /// The board of the named puzzle is invalid
+    Board(String, BoardError),
+    /// The goal tile of the named puzzle is not a power of two of at least 4
+    Goal(String, u64),
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleError::Format(error) => write!(f, "invalid puzzle pack: {}", error),
+            PuzzleError::Board(name, error) => write!(f, "puzzle \"{}\": {}", name, error),
+            PuzzleError::Goal(name, tile) => {
+                write!(
+                    f,
+                    "puzzle \"{}\": {} is not a tile that can be made",
+                    name, tile
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
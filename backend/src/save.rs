@@ -0,0 +1,128 @@
+use std::{
+    collections::VecDeque,
+    fmt::{Display, Formatter},
+};
+#[cfg(not(target_family = "wasm"))]
+use std::{fs, io, path::Path};
+
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{check_board, BoardError, Game, History};
+
+/// Version written by [`Game::save`], bumped on incompatible changes
+const SAVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct SaveData {
+    version: u32,
+    score: u64,
+    board: Vec<Vec<u64>>,
+    history: VecDeque<History>,
+    #[serde(with = "crate::config::history_length")]
+    max_history: usize,
+    rng: ChaCha12Rng,
+    seed: u64,
+    moves: usize,
+    merges: u64,
+    recorded: bool,
+}
+
+/// Reason why [`Game::load`] failed
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LoadError {
+    /// The data is not a saved game
+    Format(String),
+    /// The game was saved with an unsupported version
+    Version(u32),
+    /// The board, or one in the history, is invalid
+    Board(BoardError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Format(error) => write!(f, "invalid saved game: {}", error),
+            LoadError::Version(version) => write!(f, "unsupported save version {}", version),
+            LoadError::Board(error) => write!(f, "invalid saved board: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl Game {
+    /// Serialize the full state of the game, history and RNG included,
+    /// so that a loaded game continues exactly as this one would
+    pub fn save(&self) -> String {
+        serde_json::to_string(&SaveData {
+            version: SAVE_VERSION,
+            score: self.score,
+            board: self.board.clone(),
+            history: self.history.clone(),
+            max_history: self.max_history,
+            rng: self.rng.clone(),
+            seed: self.seed,
+            moves: self.moves,
+            merges: self.merges,
+            recorded: self.recorded,
+        })
+        .expect("games can always be serialized")
+    }
+
+    /// Restore a game written by [`Game::save`]
+    pub fn load(json: &str) -> Result<Self, LoadError> {
+        let data: SaveData =
+            serde_json::from_str(json).map_err(|e| LoadError::Format(e.to_string()))?;
+        if data.version != SAVE_VERSION {
+            return Err(LoadError::Version(data.version));
+        }
+        check_board(&data.board).map_err(LoadError::Board)?;
+        for history in &data.history {
+            check_board(&history.board).map_err(LoadError::Board)?;
+            if history.board.len() != data.board.len()
+                || history.board[0].len() != data.board[0].len()
+            {
+                return Err(LoadError::Board(BoardError::Size));
+            }
+        }
+        let mut history = data.history;
+        history.truncate(data.max_history);
+        Ok(Self {
+            score: data.score,
+            transpose: vec![vec![0; data.board.len()]; data.board[0].len()],
+            board: data.board,
+            history,
+            max_history: data.max_history,
+            rng: data.rng,
+            seed: data.seed,
+            moves: data.moves,
+            merges: data.merges,
+            suspended: true,
+            recorded: data.recorded,
+        })
+    }
+
+    /// Mark the game saved in `path` as [recorded](Game::is_recorded) if it is an earlier state
+    /// of this one, which was just recorded, so that resuming it doesn't count the game twice.
+    /// Missing files and saves of other games are left as they are
+    #[cfg(not(target_family = "wasm"))]
+    pub fn mark_save_recorded(&self, path: &Path) -> io::Result<()> {
+        let json = match fs::read_to_string(path) {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            json => json?,
+        };
+        let Ok(mut saved) = Game::load(&json) else {
+            return Ok(());
+        };
+        if saved.seed != self.seed
+            || saved.height() != self.height()
+            || saved.width() != self.width()
+            || saved.moves > self.moves
+        {
+            return Ok(());
+        }
+        saved.recorded = true;
+        fs::write(path, saved.save())
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{puzzle::Goal, Direction, Game};
+
+const DIRECTIONS: [Direction; 4] = [Direction::U, Direction::R, Direction::D, Direction::L];
+
+/// Search the shortest sequence of moves that reaches `goal` starting from `game`.
+///
+/// Spawns are deterministic, since they only depend on the RNG state carried by `game`,
+/// so the search explores the exact games a player would see.
+/// At most `max_depth` moves are tried, further limited by the moves left in `goal`.
+///
+/// Returns `None` if no solution exists within the bound
+pub fn solve(game: &Game, goal: &Goal, max_depth: usize) -> Option<Vec<Direction>> {
+    let max_depth = match goal.max_moves {
+        Some(max_moves) => max_depth.min(max_moves.saturating_sub(game.moves)),
+        None => max_depth,
+    };
+    let mut start = game.clone();
+    start.history.clear();
+    start.max_history = 0;
+    if goal.is_reached(&start) {
+        return Some(vec![]);
+    }
+
+    let mut visited = HashSet::new();
+    visited.insert(fingerprint(&start));
+    let mut queue = VecDeque::from([(start, vec![])]);
+    while let Some((game, path)) = queue.pop_front() {
+        if path.len() >= max_depth {
+            continue;
+        }
+        for direction in DIRECTIONS {
+            let mut next = game.clone();
+            if next.push(direction).is_none() || !visited.insert(fingerprint(&next)) {
+                continue;
+            }
+            let mut path = path.clone();
+            path.push(direction);
+            if goal.is_reached(&next) {
+                return Some(path);
+            }
+            queue.push_back((next, path));
+        }
+    }
+    None
+}
+
+/// Identify a state by its board and the full state of its RNG,
+/// as two games sharing both will evolve identically
+fn fingerprint(game: &Game) -> (Vec<Vec<u64>>, [u8; 32], u64, u128) {
+    let rng = &game.rng;
+    (
+        game.board.clone(),
+        rng.get_seed(),
+        rng.get_stream(),
+        rng.get_word_pos(),
+    )
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Game;
+
+/// File name used by the terminal frontends to store statistics
+pub const STATS_FILE: &str = "stats.json";
+/// Tile a game must reach to count as a win
+pub const WIN_TILE: u64 = 2048;
+/// Tiles for which a win percentage is usually shown
+pub const TARGET_TILES: [u64; 5] = [256, 512, 1024, 2048, 4096];
+
+/// Statistics accumulated over every finished game
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    pub games_played: u64,
+    pub total_score: u64,
+    pub best_score: u64,
+    pub total_moves: u64,
+    pub total_merges: u64,
+    /// Consecutive games reaching [`WIN_TILE`], up to the last one
+    pub current_streak: u64,
+    pub best_streak: u64,
+    /// Number of games ending with each max tile
+    pub max_tiles: BTreeMap<u64, u64>,
+}
+
+impl Statistics {
+    /// Add a finished game. Games without any move are ignored
+    pub fn record(&mut self, game: &Game) {
+        if game.moves() == 0 {
+            return;
+        }
+        self.games_played += 1;
+        self.total_score += game.score();
+        self.best_score = self.best_score.max(game.score());
+        self.total_moves += game.moves() as u64;
+        self.total_merges += game.merges();
+        if game.max_tile() >= WIN_TILE {
+            self.current_streak += 1;
+            self.best_streak = self.best_streak.max(self.current_streak);
+        } else {
+            self.current_streak = 0;
+        }
+        *self.max_tiles.entry(game.max_tile()).or_default() += 1;
+    }
+
+    pub fn average_score(&self) -> f64 {
+        if self.games_played == 0 {
+            0.0
+        } else {
+            self.total_score as f64 / self.games_played as f64
+        }
+    }
+
+    /// Percentage of games in which `tile` or a bigger one was reached
+    pub fn win_percentage(&self, tile: u64) -> f64 {
+        if self.games_played == 0 {
+            return 0.0;
+        }
+        let wins: u64 = self.max_tiles.range(tile..).map(|(_, count)| count).sum();
+        100.0 * wins as f64 / self.games_played as f64
+    }
+}
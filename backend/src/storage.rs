@@ -0,0 +1,58 @@
+#[cfg(not(target_family = "wasm"))]
+use std::path::PathBuf;
+use std::{fs, io, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(not(target_family = "wasm"))]
+const APP_DIR: &str = "2048";
+/// File name used by the terminal frontends to save the game in progress
+pub const SAVE_FILE: &str = "save.json";
+
+/// Path of `name` inside the per-user data directory, creating the directory if needed.
+///
+/// Returns `None` if the platform has no data directory or it can't be created
+#[cfg(not(target_family = "wasm"))]
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join(APP_DIR);
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(name))
+}
+
+/// Path of `name` inside the per-user configuration directory, creating the directory if needed.
+///
+/// Returns `None` if the platform has no configuration directory or it can't be created
+#[cfg(not(target_family = "wasm"))]
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    let dir = dirs::config_dir()?.join(APP_DIR);
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(name))
+}
+
+/// Load a JSON value from `path`.
+/// A missing file is not an error and yields the default value
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> io::Result<T> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map_err(io::Error::from),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(error) => Err(error),
+    }
+}
+
+/// Store `value` as JSON in `path`, replacing its content
+pub fn save<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(value)?)
+}
+
+/// Load the JSON value of `path`, change it with `f` and store it back.
+/// A file that can't be read or parsed is left untouched and its error returned,
+/// so that a bad read never replaces the data with the default value
+pub fn update<T, R>(path: &Path, f: impl FnOnce(&mut T) -> R) -> io::Result<R>
+where
+    T: Serialize + DeserializeOwned + Default,
+{
+    let mut value = load(path)?;
+    let result = f(&mut value);
+    save(path, &value)?;
+    Ok(result)
+}
@@ -1,4 +1,15 @@
-use crate::{Direction, Game, PushResult};
+use crate::{
+    animation::{merges, slides, AnimationSpeed, Slide},
+    config::{Config, UNLIMITED_HISTORY},
+    daily::{DailyChallenge, DailyResults, Date},
+    highscores::{HighScores, MAX_HIGHSCORES},
+    keymap::{Action, Key, Keymap, Preset},
+    palette::{self, Theme},
+    puzzle::{Goal, PuzzleError, PuzzlePack, PuzzleStatus},
+    solver::solve,
+    stats::{Statistics, WIN_TILE},
+    storage, BoardError, Direction, Game, LoadError, Move, PushResult, MAX_SIZE,
+};
 
 #[test]
 fn new() {
@@ -431,3 +442,526 @@ fn push() {
     assert_eq!(game.score, 4 + 4 + 8 + 4);
     assert_eq!(game.history.len(), 2);
 }
+
+#[test]
+fn push_non_square() {
+    let mut game = Game::from_board(
+        vec![
+            vec![0, 0, 0, 0, 0],
+            vec![2, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![2, 0, 0, 0, 4],
+        ],
+        0,
+        0,
+    )
+    .unwrap();
+    assert_eq!(
+        game.push(Direction::U),
+        Some(PushResult {
+            transitions: vec![
+                vec![
+                    ((1, 0), (3, 0)).into(),
+                    ().into(),
+                    ().into(),
+                    ().into(),
+                    (3, 4).into(),
+                ],
+                vec![().into(); 5],
+                vec![().into(); 5],
+                vec![().into(); 5],
+            ],
+            spawned_row: 3,
+            spawned_col: 0,
+            spawned_value: 2,
+            new_score: 4,
+        })
+    );
+    assert_eq!(
+        game.board,
+        vec![
+            vec![4, 0, 0, 0, 4],
+            vec![0; 5],
+            vec![0; 5],
+            vec![2, 0, 0, 0, 0],
+        ]
+    );
+    assert!(game.push(Direction::D).is_some());
+}
+
+#[test]
+fn set_max_history() {
+    let mut game = Game::from_board(vec![vec![2, 0, 0], vec![0; 3], vec![0; 3]], 3, 0).unwrap();
+    for direction in [Direction::R, Direction::D, Direction::L, Direction::U] {
+        game.push(direction);
+    }
+    assert_eq!(game.history().len(), 3);
+    game.set_max_history(1);
+    assert_eq!(game.history(), vec![Direction::U]);
+    game.set_max_history(0);
+    assert!(!game.undo());
+}
+
+#[test]
+fn history_moves() {
+    let board = vec![vec![2, 2, 0], vec![0; 3], vec![0; 3]];
+    let mut game = Game::from_board(board.clone(), 2, 0).unwrap();
+    let result = game.push(Direction::L).unwrap();
+    let spawned = (result.spawned_row, result.spawned_col, result.spawned_value);
+    assert_eq!(
+        game.history_moves(),
+        vec![Move {
+            number: 1,
+            direction: Direction::L,
+            gained: 4,
+            spawned
+        }]
+    );
+    game.push(Direction::D);
+    game.push(Direction::R);
+    let moves = game.history_moves();
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[0].number, 2);
+    assert_eq!(moves[1].direction, Direction::R);
+
+    assert!(!game.undo_to(0));
+    assert!(!game.undo_to(4));
+    assert!(game.undo_to(1));
+    assert_eq!(game.moves(), 1);
+    assert_eq!(game.score(), 4);
+    assert!(game.history_moves().is_empty());
+}
+
+#[test]
+fn daily() {
+    assert_eq!(Date::from_days(0), Date::new(1970, 1, 1).unwrap());
+    assert_eq!(Date::new(2000, 3, 1).unwrap().days(), 11017);
+    assert_eq!(Date::new(1969, 12, 31).unwrap().days(), -1);
+    for days in -1000..1000 {
+        assert_eq!(Date::from_days(days).days(), days);
+    }
+    assert!(Date::new(2023, 2, 29).is_none());
+    assert!(Date::new(2024, 2, 29).is_some());
+    assert!(Date::new(2024, 13, 1).is_none());
+    assert_eq!("2024-02-29".parse(), Ok(Date::new(2024, 2, 29).unwrap()));
+    assert!("2024-02-30".parse::<Date>().is_err());
+    assert_eq!(Date::new(812, 5, 7).unwrap().to_string(), "0812-05-07");
+
+    let date = Date::new(2024, 1, 1).unwrap();
+    let challenge = DailyChallenge::new(date);
+    assert_eq!(challenge, DailyChallenge::new(date));
+    assert_ne!(
+        challenge.seed,
+        DailyChallenge::new(Date::from_days(date.days() + 1)).seed
+    );
+    assert_eq!(challenge.game().board(), challenge.game().board());
+
+    let mut results = DailyResults::default();
+    let mut game = challenge.game();
+    assert!(results.record(date, &game));
+    assert!(!results.record(date, &game));
+    while game.score() == 0 {
+        game.push(Direction::L);
+        game.push(Direction::D);
+    }
+    assert!(results.record(date, &game));
+    assert_eq!(results.get(date).unwrap().score, game.score());
+    let json = serde_json::to_string(&results).unwrap();
+    assert!(json.contains("\"2024-01-01\""));
+    let loaded: DailyResults = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.get(date), results.get(date));
+}
+
+#[test]
+fn is_over() {
+    let mut game = Game::from_seed(3, 3, 0, 0).unwrap();
+    assert!(!game.is_over());
+    game.board = vec![vec![2, 4, 2], vec![4, 2, 4], vec![2, 4, 2]];
+    assert!(game.is_over());
+    assert_eq!(game.max_tile(), 4);
+    game.board[2][2] = 4;
+    assert!(!game.is_over());
+    game.board[2][2] = 0;
+    assert!(!game.is_over());
+}
+
+#[test]
+fn from_board() {
+    assert_eq!(
+        Game::from_board(vec![vec![0; 2]; 3], 0, 0).unwrap_err(),
+        BoardError::Size
+    );
+    assert_eq!(
+        Game::from_board(vec![vec![0; 3], vec![0; 4], vec![0; 3]], 0, 0).unwrap_err(),
+        BoardError::NotRectangular
+    );
+    assert_eq!(
+        Game::from_board(vec![vec![0, 2, 4], vec![0, 6, 0], vec![1; 3]], 0, 0).unwrap_err(),
+        BoardError::Tile {
+            row: 1,
+            col: 1,
+            value: 6
+        }
+    );
+    assert_eq!(
+        Game::from_board(vec![vec![0; 3], vec![0; 3], vec![1, 0, 0]], 0, 0).unwrap_err(),
+        BoardError::Tile {
+            row: 2,
+            col: 0,
+            value: 1
+        }
+    );
+
+    let board = vec![vec![2, 2, 0], vec![0, 0, 0], vec![0, 0, 4096]];
+    let mut game = Game::from_board(board.clone(), 1, 0).unwrap();
+    assert_eq!(game.board, board);
+    assert_eq!(game.moves(), 0);
+    assert!(game.push(Direction::L).is_some());
+    assert_eq!(game.board[0][0], 4);
+    assert_eq!(game.moves(), 1);
+    assert!(game.undo());
+    assert_eq!(game.board, board);
+    assert_eq!(game.moves(), 0);
+}
+
+#[test]
+fn puzzle() {
+    let goal = Goal {
+        tile: 8,
+        max_moves: Some(2),
+    };
+    assert_eq!(goal.to_string(), "Make a 8 in at most 2 moves");
+    let mut game = Game::from_board(vec![vec![2, 2, 4], vec![0; 3], vec![0; 3]], 0, 0).unwrap();
+    assert_eq!(goal.status(&game), PuzzleStatus::InProgress);
+    game.push(Direction::L);
+    assert_eq!(goal.status(&game), PuzzleStatus::InProgress);
+    game.push(Direction::L);
+    assert_eq!(goal.status(&game), PuzzleStatus::Solved);
+
+    let goal = Goal {
+        tile: 64,
+        max_moves: Some(2),
+    };
+    let mut game = Game::from_board(vec![vec![2, 2, 4], vec![0; 3], vec![0; 3]], 0, 0).unwrap();
+    assert!(game.push(Direction::L).is_some());
+    assert_eq!(goal.status(&game), PuzzleStatus::InProgress);
+    assert!(game.push(Direction::D).is_some());
+    assert_eq!(goal.status(&game), PuzzleStatus::Failed);
+
+    let pack = PuzzlePack::builtin();
+    assert!(!pack.puzzles.is_empty());
+    assert!(matches!(
+        PuzzlePack::from_json("{\"name\": \"\"}"),
+        Err(PuzzleError::Format(_))
+    ));
+    assert_eq!(
+        PuzzlePack::from_json(
+            r#"{"name": "", "puzzles": [{"name": "a", "goal": {"tile": 4}, "board": [[3]]}]}"#
+        ),
+        Err(PuzzleError::Board("a".to_string(), BoardError::Size))
+    );
+    for tile in [0, 2, 6] {
+        let json = r#"{"name": "", "puzzles": [{"name": "a", "goal": {"tile": TILE}, "board": [[2, 2, 0], [0, 0, 0], [0, 0, 0]]}]}"#;
+        assert_eq!(
+            PuzzlePack::from_json(&json.replace("TILE", &tile.to_string())),
+            Err(PuzzleError::Goal("a".to_string(), tile))
+        );
+    }
+}
+
+#[test]
+fn solver() {
+    for puzzle in PuzzlePack::builtin().puzzles {
+        let mut game = puzzle.game(0).unwrap();
+        let solution = solve(&game, &puzzle.goal, usize::MAX)
+            .unwrap_or_else(|| panic!("puzzle \"{}\" has no solution", puzzle.name));
+        for direction in solution {
+            assert_eq!(puzzle.goal.status(&game), PuzzleStatus::InProgress);
+            assert!(game.push(direction).is_some());
+        }
+        assert_eq!(puzzle.goal.status(&game), PuzzleStatus::Solved);
+    }
+
+    let game = Game::from_board(vec![vec![2, 2, 4], vec![0; 3], vec![0; 3]], 0, 0).unwrap();
+    let goal = |tile, max_moves| Goal { tile, max_moves };
+    assert_eq!(solve(&game, &goal(4, None), 0).unwrap().len(), 0);
+    assert_eq!(solve(&game, &goal(8, None), 10).unwrap().len(), 2);
+    assert!(solve(&game, &goal(8, Some(1)), 10).is_none());
+    assert!(solve(&game, &goal(8, None), 1).is_none());
+    assert!(solve(&game, &goal(64, Some(4)), usize::MAX).is_none());
+}
+
+#[test]
+fn highscores() {
+    let date = Date::new(2024, 1, 1).unwrap();
+    let mut highscores = HighScores::default();
+    let mut game = Game::from_board(vec![vec![2, 2, 4], vec![0; 3], vec![0; 3]], 0, 7).unwrap();
+    assert_eq!(highscores.record(&game, date), None);
+    game.push(Direction::L);
+    assert_eq!(highscores.record(&game, date), Some(0));
+    game.push(Direction::L);
+    assert_eq!(highscores.record(&game, date), Some(0));
+    assert_eq!(highscores.record(&game, date), Some(1));
+    let scores = highscores.get(3, 3);
+    assert_eq!(scores.len(), 3);
+    assert_eq!(scores[0].score, 12);
+    assert_eq!(scores[0].max_tile, 8);
+    assert_eq!(scores[0].moves, 2);
+    assert_eq!(scores[0].seed, 7);
+    assert_eq!(scores[2].score, 4);
+    assert!(highscores.get(4, 4).is_empty());
+
+    for _ in 0..MAX_HIGHSCORES {
+        highscores.record(&game, date);
+    }
+    assert_eq!(highscores.get(3, 3).len(), MAX_HIGHSCORES);
+    assert_eq!(highscores.record(&game, date), None);
+    let json = serde_json::to_string(&highscores).unwrap();
+    let loaded: HighScores = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.get(3, 3), highscores.get(3, 3));
+}
+
+#[test]
+fn stats() {
+    let mut stats = Statistics::default();
+    assert_eq!(stats.average_score(), 0.0);
+    assert_eq!(stats.win_percentage(WIN_TILE), 0.0);
+
+    let mut game = Game::from_board(vec![vec![2, 2, 4], vec![0; 3], vec![0; 3]], 1, 0).unwrap();
+    stats.record(&game);
+    assert_eq!(stats.games_played, 0);
+    game.push(Direction::L);
+    game.push(Direction::L);
+    assert_eq!(game.merges(), 2);
+    game.undo();
+    assert_eq!(game.merges(), 1);
+    stats.record(&game);
+
+    let mut game =
+        Game::from_board(vec![vec![1024, 1024, 0], vec![0; 3], vec![0; 3]], 0, 0).unwrap();
+    game.push(Direction::L);
+    stats.record(&game);
+    stats.record(&game);
+
+    assert_eq!(stats.games_played, 3);
+    assert_eq!(stats.total_score, 4 + 2048 * 2);
+    assert_eq!(stats.best_score, 2048);
+    assert_eq!(stats.total_moves, 3);
+    assert_eq!(stats.total_merges, 3);
+    assert_eq!(stats.current_streak, 2);
+    assert_eq!(stats.best_streak, 2);
+    assert_eq!(stats.max_tiles.get(&4), Some(&1));
+    assert_eq!(stats.max_tiles.get(&WIN_TILE), Some(&2));
+    assert_eq!(stats.average_score(), (4.0 + 2048.0 * 2.0) / 3.0);
+    assert_eq!(stats.win_percentage(4), 100.0);
+    assert_eq!(stats.win_percentage(WIN_TILE * 2), 0.0);
+    assert!((stats.win_percentage(WIN_TILE) - 200.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn save() {
+    let mut game = Game::from_seed(4, 5, 3, 42).unwrap();
+    for direction in [Direction::L, Direction::D, Direction::R, Direction::U] {
+        game.push(direction);
+    }
+    assert!(!game.is_suspended());
+    let mut loaded = Game::load(&game.save()).unwrap();
+    assert!(loaded.is_suspended() && !game.is_suspended());
+    assert_eq!(loaded.board, game.board);
+    assert_eq!(loaded.score(), game.score());
+    assert_eq!(loaded.moves(), game.moves());
+    assert_eq!(loaded.merges(), game.merges());
+    assert_eq!(loaded.seed(), game.seed());
+    assert_eq!(loaded.history.len(), game.history.len());
+    for direction in [Direction::D, Direction::L, Direction::U, Direction::R] {
+        assert_eq!(loaded.push(direction), game.push(direction));
+    }
+    assert!(!loaded.is_suspended());
+    loaded.suspend();
+    assert!(loaded.undo() && !loaded.is_suspended());
+    assert!(loaded.push(Direction::R).is_some());
+    while game.undo() {
+        assert!(loaded.undo());
+        assert_eq!(loaded.board, game.board);
+    }
+    assert!(!loaded.undo());
+
+    let path = std::env::temp_dir().join(format!("2048-save-{}.json", std::process::id()));
+    let mut game = Game::from_seed(4, 4, 0, 5).unwrap();
+    game.push(Direction::L);
+    std::fs::write(&path, game.save()).unwrap();
+    Game::from_seed(4, 4, 0, 6)
+        .unwrap()
+        .mark_save_recorded(&path)
+        .unwrap();
+    assert!(!Game::load(&std::fs::read_to_string(&path).unwrap())
+        .unwrap()
+        .is_recorded());
+    game.push(Direction::U);
+    game.mark_save_recorded(&path).unwrap();
+    let resumed = Game::load(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(resumed.is_recorded());
+    assert_eq!(resumed.moves(), 1);
+    std::fs::remove_file(&path).unwrap();
+    assert!(game.mark_save_recorded(&path).is_ok());
+
+    let unlimited = Game::from_seed(4, 4, UNLIMITED_HISTORY, 1).unwrap().save();
+    assert!(unlimited.contains(r#""max_history":"unlimited""#));
+    assert_eq!(
+        Game::load(&unlimited).unwrap().max_history(),
+        UNLIMITED_HISTORY
+    );
+
+    assert!(matches!(Game::load("{}"), Err(LoadError::Format(_))));
+    let json = game.save().replace("\"version\":1", "\"version\":99");
+    assert_eq!(Game::load(&json).unwrap_err(), LoadError::Version(99));
+    let mut broken = game.clone();
+    broken.board[0][0] = 3;
+    assert!(matches!(
+        Game::load(&broken.save()),
+        Err(LoadError::Board(BoardError::Tile { .. }))
+    ));
+}
+
+#[test]
+fn animation() {
+    let board = vec![vec![2, 2, 4], vec![0, 0, 8], vec![0; 3]];
+    let mut game = Game::from_board(board.clone(), 0, 0).unwrap();
+    let result = game.push(Direction::L).unwrap();
+    let mut slides = slides(&board, &result);
+    slides.sort_by_key(|s| (s.from, s.to));
+    assert_eq!(
+        slides,
+        vec![
+            Slide {
+                from: (0, 0),
+                to: (0, 0),
+                value: 2
+            },
+            Slide {
+                from: (0, 1),
+                to: (0, 0),
+                value: 2
+            },
+            Slide {
+                from: (0, 2),
+                to: (0, 1),
+                value: 4
+            },
+            Slide {
+                from: (1, 2),
+                to: (1, 0),
+                value: 8
+            },
+        ]
+    );
+    assert_eq!(merges(&result), vec![(0, 0)]);
+    assert_eq!(slides[3].position(0.0), (1.0, 2.0));
+    assert_eq!(slides[3].position(0.5), (1.0, 1.0));
+    assert_eq!(slides[3].position(2.0), (1.0, 0.0));
+}
+
+#[test]
+fn keymap() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.action(Key::Char('w')), Some(Action::Up));
+    assert_eq!(keymap.action(Key::Left), Some(Action::Left));
+    assert_eq!(keymap.action(Key::Char('h')), None);
+    assert_eq!(keymap.describe_moves(), "wasd or arrows");
+
+    let vim = Keymap::from_preset(Preset::Vim);
+    assert_eq!(vim.action(Key::Char('h')), Some(Action::Left));
+    assert_eq!(vim.action(Key::Char('u')), Some(Action::Undo));
+    assert_eq!(vim.action(Key::Char('z')), None);
+    assert_eq!(vim.describe_moves(), "khjl or arrows");
+
+    let remapped: Keymap =
+        serde_json::from_str(r#"{"preset": "vim", "bindings": {"undo": ["h", "z"]}}"#).unwrap();
+    assert_eq!(remapped.action(Key::Char('h')), Some(Action::Undo));
+    assert_eq!(remapped.action(Key::Char('z')), Some(Action::Undo));
+    assert_eq!(remapped.action(Key::Char('u')), None);
+    assert_eq!(remapped.describe(Action::Undo), "h/z");
+    assert_eq!("ArrowDown".parse(), Ok(Key::Down));
+    assert!("F1".parse::<Key>().is_err());
+    assert!(serde_json::from_str::<Keymap>(r#"{"bindings": {"jump": ["x"]}}"#).is_err());
+}
+
+#[test]
+fn config() {
+    let config = Config::default();
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+
+    let config: Config = serde_json::from_str(
+        r#"{"width": 50, "animation_speed": "off", "keymap": {"preset": "vim"}}"#,
+    )
+    .unwrap();
+    assert_eq!(config.height, Config::default().height);
+    assert_eq!(config.animation_speed, AnimationSpeed::Off);
+    assert_eq!(config.keymap, Keymap::from_preset(Preset::Vim));
+    let game = config.game(Some(3));
+    assert_eq!(game.width(), MAX_SIZE);
+    assert_eq!(game.seed(), 3);
+    assert!(serde_json::from_str::<Config>(r#"{"theme": "neon"}"#).is_err());
+
+    let unlimited = Config {
+        history: UNLIMITED_HISTORY,
+        ..Config::default()
+    };
+    let json = serde_json::to_string(&unlimited).unwrap();
+    assert!(json.contains(r#""history":"unlimited""#));
+    assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), unlimited);
+    assert!(serde_json::from_str::<Config>(r#"{"history": "all"}"#).is_err());
+}
+
+#[test]
+fn themes() {
+    let classic = Theme::Classic.builtin_palette().unwrap();
+    assert_eq!(classic.background(0), (204, 192, 179));
+    assert_eq!(classic.background(2), (238, 228, 218));
+    assert_eq!(classic.background(2048), (237, 194, 46));
+    for value in [4096, 8192, 1 << 20] {
+        assert_eq!(classic.background(value), (60, 58, 50));
+        assert_eq!(classic.foreground(value), palette::LIGHT_TEXT);
+    }
+    assert_eq!(classic.foreground(4), palette::DARK_TEXT);
+    assert_eq!(classic.foreground(8), palette::LIGHT_TEXT);
+    for theme in Theme::BUILTIN {
+        let palette = theme.builtin_palette().unwrap();
+        let last = 1 << palette.tiles.len();
+        assert_eq!(palette.background(0), palette.empty);
+        assert_eq!(palette.background(last), *palette.tiles.last().unwrap());
+        assert_eq!(
+            palette.background(last / 2),
+            palette.tiles[palette.tiles.len() - 2]
+        );
+        for value in [last * 2, last * 1024, 1 << 63] {
+            assert_eq!(palette.background(value), palette.background(last));
+            assert_eq!(palette.foreground(value), palette.foreground(last));
+        }
+    }
+    assert!(Theme::Custom("mine".to_string())
+        .builtin_palette()
+        .is_none());
+    assert_eq!(
+        serde_json::from_str::<Theme>(r#"{"custom": "mine"}"#).unwrap(),
+        Theme::Custom("mine".to_string())
+    );
+}
+
+#[test]
+fn storage_update() {
+    let path = std::env::temp_dir().join(format!("2048-storage-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let score = storage::update(&path, |stats: &mut Statistics| {
+        stats.best_score = 8;
+        stats.best_score
+    });
+    assert_eq!(score.unwrap(), 8);
+    assert_eq!(storage::load::<Statistics>(&path).unwrap().best_score, 8);
+
+    std::fs::write(&path, "{ not json").unwrap();
+    assert!(storage::update(&path, |stats: &mut Statistics| stats.best_score = 16).is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ not json");
+    std::fs::remove_file(&path).unwrap();
+}
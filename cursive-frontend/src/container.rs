@@ -1,108 +1,790 @@
-use backend::{Direction, Game};
+use std::{fs, rc::Rc, thread, time::Instant};
+
+use backend::{
+    animation::{self, AnimationSpeed, Slide},
+    config::{Config, CONFIG_FILE, UNLIMITED_HISTORY},
+    daily::{DailyChallenge, DailyResults, Date, DAILY_FILE},
+    highscores::{HighScores, HIGHSCORES_FILE},
+    keymap::{self, Action, Keymap, Preset},
+    palette::{Palette, Rgb, Theme},
+    puzzle::{Puzzle, PuzzlePack, PuzzleStatus},
+    stats::{Statistics, STATS_FILE, TARGET_TILES, WIN_TILE},
+    storage, Direction, Game, Move,
+};
 use cursive::{
-    event::{AnyCb, Event, EventResult, Key},
-    theme::{Color, ColorStyle, PaletteColor},
-    traits::{Nameable, Resizable},
-    view::{Selector, SizeConstraint},
-    views::{Dialog, LinearLayout, SliderView, TextView},
-    Printer, Vec2, View,
+    event::{Event, EventResult, Key},
+    theme::{self as cursive_theme, Color, ColorStyle, PaletteColor},
+    traits::{Nameable, Resizable, Scrollable},
+    view::{ScrollStrategy, SizeConstraint},
+    views::{
+        Checkbox, Dialog, EditView, LinearLayout, Panel, ScrollView, SelectView, SliderView,
+        TextView,
+    },
+    Cursive, Printer, Vec2, View,
 };
-use lazy_static::lazy_static;
 
-const HISTORY_WIDTH: usize = 13;
-const HISTORY_HEIGHT: usize = 7;
+const HISTORY_WIDTH: usize = 28;
+const HISTORY_HEIGHT: usize = 9;
+/// Digits of the history length field, enough for any number of moves played in practice
+const HISTORY_DIGITS: usize = 9;
 const SCORE_WIDTH: usize = 11;
 const SCORE_HEIGHT: usize = 3;
 const INTER_SPACE: usize = 2;
 const OUTER_SPACE: usize = 1;
 const CELL_EXT_WIDTH: usize = 13;
 const CELL_EXT_HEIGHT: usize = 7;
+/// Actions explained in the instructions, after the moves
+const INSTRUCTIONS: [(Action, &str); 9] = [
+    (Action::NewGame, "start a new game"),
+    (Action::Daily, "play the daily challenge"),
+    (Action::Puzzles, "play a puzzle"),
+    (Action::HighScores, "show high scores"),
+    (Action::Stats, "show statistics"),
+    (Action::Animations, "toggle animations"),
+    (Action::Settings, "change settings"),
+    (Action::Undo, "undo"),
+    (Action::Quit, "quit"),
+];
 
-lazy_static! {
-    static ref COLORS: [ColorStyle; 13] = [
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(204, 192, 179)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(238, 228, 218)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(237, 224, 200)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(242, 177, 121)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(245, 149, 99)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(246, 124, 95)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(246, 94, 59)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(237, 207, 114)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(237, 204, 97)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(237, 200, 80)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(237, 197, 63)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(237, 194, 46)),
-        ColorStyle::new(PaletteColor::Background, Color::Rgb(0, 0, 0)),
-    ];
+/// Tiles moved by the last push, drawn over time instead of the board
+struct Animation {
+    slides: Vec<Slide>,
+    merges: Vec<(usize, usize)>,
+    spawned: (usize, usize),
+    start: Instant,
 }
 
+/// Something to do with the container once the player agreed to discard the game in progress
+type Discard = Rc<dyn Fn(&mut Container) -> EventResult>;
+
 pub(crate) struct Container {
     game: Game,
-    sliders: LinearLayout,
-    next_width: usize,
-    next_height: usize,
-    next_history: usize,
+    /// Moves that can be undone, selecting one goes back to the state after it
+    history: Panel<ScrollView<SelectView<usize>>>,
+    /// Seed, moves and history length of the game when `history` was filled
+    history_shown: Option<(u64, usize, usize)>,
+    /// Whether keys go to `history` instead of the game, toggled with Tab
+    history_focus: bool,
+    /// Settings of the next games, saved in the configuration file from the dialogs
+    config: Config,
+    /// Colors of `config.theme`
+    palette: Palette,
+    daily: Option<Date>,
+    puzzles: PuzzlePack,
+    puzzle: Option<Puzzle>,
+    animation: Option<Animation>,
+    /// Whether the win dialog was already shown for this game
+    won: bool,
+    /// Problems met while recording finished games, not shown yet
+    errors: Vec<String>,
 }
 
 impl Container {
-    pub(crate) fn new() -> Container {
-        let game = Game::default();
-        let (game_width, game_height, game_history) =
-            (game.width(), game.height(), game.max_history());
-
-        let mut sliders = LinearLayout::vertical();
-        sliders.add_child(
-            Dialog::around(
-                SliderView::horizontal((backend::MIN_SIZE..=backend::MAX_SIZE).count())
-                    .value(game_width - backend::MIN_SIZE)
-                    .on_change(|s, v| {
-                        let title = format!("Width: {}", v + backend::MIN_SIZE);
-                        s.call_on_name("width", |view: &mut Dialog| view.set_title(title));
-                        s.call_on_name("container", |view: &mut Container| {
-                            view.next_width = v + backend::MIN_SIZE;
-                        });
-                    }),
+    pub(crate) fn new(puzzles: PuzzlePack, config: Config, palette: Palette) -> Container {
+        let game = config.game(None);
+        let history = SelectView::new()
+            .on_submit(|s, &moves: &usize| with_container(s, |view| view.jump_to(moves)));
+        Container {
+            game,
+            history: Panel::new(
+                ScrollView::new(history).scroll_strategy(ScrollStrategy::StickToBottom),
             )
-            .title(format!("Width: {}", game_width))
-            .with_name("width"),
+            .title("History"),
+            history_shown: None,
+            history_focus: false,
+            config,
+            palette,
+            daily: None,
+            puzzles,
+            puzzle: None,
+            animation: None,
+            won: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Record the result of the current game, unless it is a puzzle, only suspended
+    /// or already recorded, and mark its save as recorded.
+    /// Files that fail to load are kept as they are, and the errors shown by the next event
+    pub(crate) fn finish(&mut self) {
+        let daily = self.daily.take();
+        if self.puzzle.take().is_some() || self.game.is_suspended() || self.game.is_recorded() {
+            return;
+        }
+        if let (Some(date), Some(path)) = (daily, storage::data_file(DAILY_FILE)) {
+            if let Err(error) = storage::update(&path, |results: &mut DailyResults| {
+                results.record(date, &self.game)
+            }) {
+                self.errors.push(format!(
+                    "Could not record the daily result in {}: {}",
+                    path.display(),
+                    error
+                ));
+            }
+        }
+        if let Some(path) = storage::data_file(HIGHSCORES_FILE) {
+            if let Err(error) = storage::update(&path, |highscores: &mut HighScores| {
+                highscores.record(&self.game, Date::today())
+            }) {
+                self.errors.push(format!(
+                    "Could not record the high score in {}: {}",
+                    path.display(),
+                    error
+                ));
+            }
+        }
+        if let Some(path) = storage::data_file(STATS_FILE) {
+            if let Err(error) =
+                storage::update(&path, |stats: &mut Statistics| stats.record(&self.game))
+            {
+                self.errors.push(format!(
+                    "Could not record the statistics in {}: {}",
+                    path.display(),
+                    error
+                ));
+            }
+        }
+        if let Some(path) = storage::data_file(storage::SAVE_FILE) {
+            if let Err(error) = self.game.mark_save_recorded(&path) {
+                self.errors.push(format!(
+                    "Could not mark the saved game in {} as recorded: {}",
+                    path.display(),
+                    error
+                ));
+            }
+        }
+    }
+
+    /// Add a dialog with the pending errors to `result`
+    fn report_errors(&mut self, result: EventResult) -> EventResult {
+        if self.errors.is_empty() {
+            return result;
+        }
+        let text = self.errors.drain(..).collect::<Vec<_>>().join("\n");
+        result.and(EventResult::with_cb(move |s| {
+            s.add_layer(Dialog::info(text.clone()).title("Error"))
+        }))
+    }
+
+    /// Record the current game and replace it with `game`
+    fn start(&mut self, game: Game) {
+        self.finish();
+        self.won = game.max_tile() >= WIN_TILE;
+        self.animation = None;
+        self.game = game;
+    }
+
+    fn new_game(&mut self) {
+        self.start(self.config.game(None));
+    }
+
+    /// Play the current game again from the start
+    fn replay(&mut self) {
+        let game = Game::from_seed(
+            self.game.height(),
+            self.game.width(),
+            self.game.max_history(),
+            self.game.seed(),
+        )
+        .expect("the size is already valid");
+        let (daily, puzzle) = (self.daily, self.puzzle.clone());
+        self.start(game);
+        self.daily = daily;
+        if let Some(puzzle) = puzzle {
+            self.start_puzzle(puzzle);
+        }
+    }
+
+    fn start_daily(&mut self) {
+        let challenge = DailyChallenge::new(Date::today());
+        self.start(challenge.game());
+        self.daily = Some(challenge.date);
+    }
+
+    fn start_puzzle(&mut self, puzzle: Puzzle) {
+        self.start(
+            puzzle
+                .game(self.config.history)
+                .expect("puzzles are validated when loaded"),
         );
-        sliders.add_child(
-            Dialog::around(
-                SliderView::horizontal((backend::MIN_SIZE..=backend::MAX_SIZE).count())
-                    .value(game_height - backend::MIN_SIZE)
-                    .on_change(|s, v| {
-                        let title = format!("Height: {}", v + backend::MIN_SIZE);
-                        s.call_on_name("height", |view: &mut Dialog| view.set_title(title));
+        self.puzzle = Some(puzzle);
+    }
+
+    /// Go back to the state after move number `moves`
+    fn jump_to(&mut self, moves: usize) -> EventResult {
+        self.animation = None;
+        self.game.undo_to(moves);
+        EventResult::Consumed(None)
+    }
+
+    /// Lines of the history panel, with the number of the move they stand for
+    fn history_items(&self) -> Vec<(String, usize)> {
+        self.game
+            .history_moves()
+            .into_iter()
+            .map(
+                |Move {
+                     number,
+                     direction,
+                     gained,
+                     spawned: (row, col, value),
+                 }| {
+                    let label = format!(
+                        "{:>3} {} +{:<5} {} at {},{}",
+                        number,
+                        direction_symbol(direction),
+                        gained,
+                        value,
+                        row + 1,
+                        col + 1
+                    );
+                    (label, number)
+                },
+            )
+            .collect()
+    }
+
+    /// Refill the history panel if the game changed since it was last filled
+    fn refresh_history(&mut self) {
+        let shown = (
+            self.game.seed(),
+            self.game.moves(),
+            self.game.history().len(),
+        );
+        if self.history_shown == Some(shown) {
+            return;
+        }
+        self.history_shown = Some(shown);
+        let items = self.history_items();
+        self.history.set_title(match items.len() {
+            0 => "History".to_string(),
+            1 => "History: 1 undo left".to_string(),
+            undos => format!("History: {} undos left", undos),
+        });
+        let select = self.history.get_inner_mut().get_inner_mut();
+        select.clear();
+        if items.is_empty() {
+            select.add_item("no history", self.game.moves());
+        } else {
+            select.add_all(items);
+            select.set_selection(select.len() - 1);
+        }
+    }
+
+    /// Dialog listing the moves that can be undone, to go back to the state after one of them
+    fn show_jump_back(&self) -> EventResult {
+        let items = self.history_items();
+        EventResult::with_cb(move |s| {
+            if items.is_empty() {
+                s.add_layer(Dialog::info("No moves to undo"));
+                return;
+            }
+            let mut select = SelectView::new().on_submit(|s, &moves: &usize| {
+                s.pop_layer();
+                with_container(s, |view| view.jump_to(moves));
+            });
+            select.add_all(items.clone());
+            select.set_selection(select.len() - 1);
+            s.add_layer(
+                Dialog::around(select.scrollable())
+                    .title("Go back to the state after")
+                    .dismiss_button("Cancel"),
+            )
+        })
+    }
+
+    /// Whether moves were played in the current game and more are possible
+    fn in_progress(&self) -> bool {
+        self.game.moves() > 0 && !self.game.is_over()
+    }
+
+    /// Run `then` right away, or after asking if the game in progress can be discarded
+    fn confirm_discard(
+        &mut self,
+        then: impl Fn(&mut Container) -> EventResult + 'static,
+    ) -> EventResult {
+        if !self.in_progress() {
+            return then(self);
+        }
+        let then: Discard = Rc::new(then);
+        EventResult::with_cb(move |s| {
+            let then = then.clone();
+            s.add_layer(
+                Dialog::text("Discard the game in progress?")
+                    .title("Confirm")
+                    .button("Discard", move |s| {
+                        s.pop_layer();
+                        with_container(s, |view| then(view));
+                    })
+                    .dismiss_button("Cancel"),
+            )
+        })
+    }
+
+    fn save_game(&mut self) -> EventResult {
+        let message = match storage::data_file(storage::SAVE_FILE) {
+            Some(path) => match fs::write(&path, self.game.save()) {
+                Ok(()) => {
+                    self.game.suspend();
+                    format!("Saved to {}", path.display())
+                }
+                Err(error) => format!("Could not save: {}", error),
+            },
+            None => "No place to save the game".to_string(),
+        };
+        EventResult::with_cb(move |s| s.add_layer(Dialog::info(message.clone())))
+    }
+
+    /// Replace the current game with the one saved by [`Container::save_game`]
+    fn load_game(&mut self) -> EventResult {
+        let loaded = storage::data_file(storage::SAVE_FILE)
+            .ok_or_else(|| "no place to load the game from".to_string())
+            .and_then(|path| fs::read_to_string(path).map_err(|e| e.to_string()))
+            .and_then(|json| Game::load(&json).map_err(|e| e.to_string()));
+        match loaded {
+            Ok(game) => self.confirm_discard(move |view| {
+                view.start(game.clone());
+                EventResult::Consumed(None)
+            }),
+            Err(error) => EventResult::with_cb(move |s| {
+                s.add_layer(Dialog::info(format!("Could not load the game: {}", error)))
+            }),
+        }
+    }
+
+    fn select_puzzle(&self) -> EventResult {
+        let pack = self.puzzles.clone();
+        EventResult::with_cb(move |s| {
+            let mut select = SelectView::new().on_submit(|s, puzzle: &Puzzle| {
+                let puzzle = puzzle.clone();
+                s.pop_layer();
+                with_container(s, |view| {
+                    view.confirm_discard(move |view| {
+                        view.start_puzzle(puzzle.clone());
+                        EventResult::Consumed(None)
+                    })
+                });
+            });
+            for puzzle in &pack.puzzles {
+                select.add_item(format!("{}: {}", puzzle.name, puzzle.goal), puzzle.clone());
+            }
+            s.add_layer(
+                Dialog::around(select)
+                    .title(pack.name.clone())
+                    .dismiss_button("Cancel"),
+            )
+        })
+    }
+
+    fn show_highscores(&self) -> EventResult {
+        let (height, width) = (self.game.height(), self.game.width());
+        let highscores: HighScores = storage::data_file(HIGHSCORES_FILE)
+            .and_then(|path| storage::load(&path).ok())
+            .unwrap_or_default();
+        let scores = highscores.get(height, width);
+        let text = if scores.is_empty() {
+            "no scores yet".to_string()
+        } else {
+            let mut text = format!(
+                "{:>2}  {:>8}  {:>6}  {:>5}  {:>20}  {:<10}",
+                "#", "score", "tile", "moves", "seed", "date"
+            );
+            for (i, score) in scores.iter().enumerate() {
+                text.push_str(&format!(
+                    "\n{:>2}  {:>8}  {:>6}  {:>5}  {:>20}  {}",
+                    i + 1,
+                    score.score,
+                    score.max_tile,
+                    score.moves,
+                    score.seed,
+                    score.date
+                ));
+            }
+            text
+        };
+        let title = format!("High scores {}x{}", height, width);
+        EventResult::with_cb(move |s| {
+            s.add_layer(
+                Dialog::text(text.clone())
+                    .title(title.clone())
+                    .dismiss_button("Close"),
+            )
+        })
+    }
+
+    fn show_stats(&self) -> EventResult {
+        let stats: Statistics = storage::data_file(STATS_FILE)
+            .and_then(|path| storage::load(&path).ok())
+            .unwrap_or_default();
+        let mut text = format!(
+            "games played: {}\naverage score: {:.0}\nbest score: {}\ntotal moves: {}\ntotal merges: {}\nstreak of {}s: {} (best {})\n\nwin %",
+            stats.games_played,
+            stats.average_score(),
+            stats.best_score,
+            stats.total_moves,
+            stats.total_merges,
+            WIN_TILE,
+            stats.current_streak,
+            stats.best_streak
+        );
+        for tile in TARGET_TILES {
+            text.push_str(&format!(
+                "\n{:>6}: {:>5.1}%",
+                tile,
+                stats.win_percentage(tile)
+            ));
+        }
+        text.push_str("\n\nmax tile");
+        let most = stats.max_tiles.values().copied().max().unwrap_or(0);
+        for (tile, &count) in &stats.max_tiles {
+            let bar = "\u{2588}".repeat((count * 20).div_ceil(most) as usize);
+            text.push_str(&format!("\n{:>6}: {} {}", tile, bar, count));
+        }
+        EventResult::with_cb(move |s| {
+            s.add_layer(
+                Dialog::around(TextView::new(text.clone()).scrollable())
+                    .title("Statistics")
+                    .dismiss_button("Close"),
+            )
+        })
+    }
+
+    /// Dialog to change the size of the next games, saved in the configuration file.
+    /// Starting a game with it discards the current one
+    fn show_size(&self) -> EventResult {
+        let (width, height) = (self.config.width, self.config.height);
+        EventResult::with_cb(move |s| {
+            let slider = |name: &'static str, value: usize| {
+                Dialog::around(
+                    SliderView::horizontal((backend::MIN_SIZE..=backend::MAX_SIZE).count())
+                        .value(
+                            value.clamp(backend::MIN_SIZE, backend::MAX_SIZE) - backend::MIN_SIZE,
+                        )
+                        .on_change(move |s, v| {
+                            let title = format!("{}: {}", name, v + backend::MIN_SIZE);
+                            s.call_on_name(name, |view: &mut Dialog| view.set_title(title));
+                        })
+                        .with_name(format!("{} slider", name)),
+                )
+                .title(format!("{}: {}", name, value))
+                .with_name(name)
+            };
+            s.add_layer(
+                Dialog::around(
+                    LinearLayout::vertical()
+                        .child(slider("Width", width))
+                        .child(slider("Height", height)),
+                )
+                .title("Size")
+                .button("New game", |s| {
+                    let value = |s: &mut Cursive, name: &str| {
+                        s.call_on_name(&format!("{} slider", name), |view: &mut SliderView| {
+                            view.get_value() + backend::MIN_SIZE
+                        })
+                        .unwrap_or(backend::DEFAULT_SIZE)
+                    };
+                    let (width, height) = (value(s, "Width"), value(s, "Height"));
+                    s.pop_layer();
+                    with_container(s, |view| {
+                        view.config.width = width;
+                        view.config.height = height;
+                        view.save_config();
+                        view.confirm_discard(|view| {
+                            view.new_game();
+                            EventResult::Consumed(None)
+                        })
+                    });
+                })
+                .dismiss_button("Cancel"),
+            )
+        })
+    }
+
+    /// Dialog to change the history length, as a number of moves or unlimited,
+    /// of the next games and of the current one unless it is a daily challenge
+    fn show_history(&self) -> EventResult {
+        let history = self.config.history;
+        let unlimited = history == UNLIMITED_HISTORY;
+        EventResult::with_cb(move |s| {
+            let mut edit =
+                EditView::new()
+                    .max_content_width(HISTORY_DIGITS)
+                    .on_edit(|s, content, _| {
+                        let digits: String = content.chars().filter(char::is_ascii_digit).collect();
+                        if digits != content {
+                            s.call_on_name("history edit", |view: &mut EditView| {
+                                view.set_content(digits)
+                            });
+                        }
+                    });
+            if !unlimited {
+                edit.set_content(history.to_string());
+            }
+            edit.set_enabled(!unlimited);
+            let mut checkbox = Checkbox::new().on_change(|s, checked| {
+                s.call_on_name("history edit", |view: &mut EditView| {
+                    view.set_enabled(!checked)
+                });
+            });
+            checkbox.set_checked(unlimited);
+            s.add_layer(
+                Dialog::around(
+                    LinearLayout::vertical()
+                        .child(TextView::new("Moves that can be undone, 0 disables undo"))
+                        .child(
+                            edit.with_name("history edit")
+                                .fixed_width(HISTORY_DIGITS + 1),
+                        )
+                        .child(
+                            LinearLayout::horizontal()
+                                .child(checkbox.with_name("history unlimited"))
+                                .child(TextView::new(" Unlimited")),
+                        ),
+                )
+                .title("History")
+                .button("Apply", |s| {
+                    let unlimited = s
+                        .call_on_name("history unlimited", |view: &mut Checkbox| view.is_checked())
+                        .unwrap_or(false);
+                    let history = if unlimited {
+                        Some(UNLIMITED_HISTORY)
+                    } else {
+                        s.call_on_name("history edit", |view: &mut EditView| {
+                            view.get_content().parse().ok()
+                        })
+                        .flatten()
+                    };
+                    let Some(history) = history else {
+                        s.add_layer(Dialog::info("Enter a number of moves"));
+                        return;
+                    };
+                    s.pop_layer();
+                    s.call_on_name("container", |view: &mut Container| {
+                        view.config.history = history;
+                        // daily challenges are played without undo by everyone
+                        if view.daily.is_none() {
+                            view.game.set_max_history(history);
+                        }
+                        view.save_config();
+                    });
+                })
+                .dismiss_button("Cancel"),
+            )
+        })
+    }
+
+    fn show_help(&self) -> EventResult {
+        let text = self.instructions();
+        EventResult::with_cb(move |s| {
+            s.add_layer(
+                Dialog::text(text.clone())
+                    .title("Keys")
+                    .dismiss_button("Close"),
+            )
+        })
+    }
+
+    /// Dialog to change the theme, animations and keys, and save them in the configuration file
+    fn show_settings(&self) -> EventResult {
+        let config = self.config.clone();
+        EventResult::with_cb(move |s| {
+            let select = |name: &str, values: Vec<String>, selected: usize| {
+                let mut view = SelectView::new().popup();
+                view.add_all_str(values);
+                view.set_selection(selected);
+                LinearLayout::horizontal()
+                    .child(TextView::new(format!("{:<12}", name)))
+                    .child(view.with_name(name))
+            };
+            let position = |values: &[String], value: String| {
+                values.iter().position(|v| *v == value).unwrap_or(0)
+            };
+            let all_themes = Theme::all();
+            let themes: Vec<_> = all_themes.iter().map(Theme::to_string).collect();
+            let speeds: Vec<_> = AnimationSpeed::ALL
+                .iter()
+                .map(AnimationSpeed::to_string)
+                .collect();
+            let presets: Vec<_> = Preset::ALL.iter().map(Preset::to_string).collect();
+            let layout = LinearLayout::vertical()
+                .child(select(
+                    "Theme",
+                    themes.clone(),
+                    position(&themes, config.theme.to_string()),
+                ))
+                .child(select(
+                    "Animations",
+                    speeds.clone(),
+                    position(&speeds, config.animation_speed.to_string()),
+                ))
+                .child(select(
+                    "Keys",
+                    presets.clone(),
+                    position(&presets, config.keymap.preset.to_string()),
+                ))
+                .child(TextView::new("\nChanging the keys drops remapped ones"));
+            s.add_layer(
+                Dialog::around(layout)
+                    .title("Settings")
+                    .button("Save", move |s| {
+                        let selected = |s: &mut Cursive, name: &str| {
+                            s.call_on_name(name, |view: &mut SelectView| view.selected_id())
+                                .flatten()
+                                .unwrap_or(0)
+                        };
+                        let theme = all_themes[selected(s, "Theme")].clone();
+                        let palette = match theme.palette() {
+                            Ok(palette) => palette,
+                            Err(error) => {
+                                s.add_layer(Dialog::info(format!(
+                                    "Could not load theme {}: {}",
+                                    theme, error
+                                )));
+                                return;
+                            }
+                        };
+                        apply_theme(s, &palette);
+                        let speed = AnimationSpeed::ALL[selected(s, "Animations")];
+                        let preset = Preset::ALL[selected(s, "Keys")];
+                        s.pop_layer();
                         s.call_on_name("container", |view: &mut Container| {
-                            view.next_height = v + backend::MIN_SIZE;
+                            view.config.theme = theme;
+                            view.palette = palette;
+                            view.config.animation_speed = speed;
+                            if view.config.keymap.preset != preset {
+                                view.config.keymap = Keymap::from_preset(preset);
+                            }
+                            view.save_config()
                         });
-                    }),
+                    })
+                    .dismiss_button("Cancel"),
             )
-            .title(format!("Height: {}", game_height))
-            .with_name("height"),
-        );
-        sliders.add_child(
-            Dialog::around(
-                SliderView::horizontal(6)
-                    .value(game_history)
-                    .on_change(|s, v| {
-                        let title = format!("History: {}", v);
-                        s.call_on_name("history", |view: &mut Dialog| view.set_title(title));
+        })
+    }
+
+    fn save_config(&self) {
+        if let Some(path) = storage::config_file(CONFIG_FILE) {
+            let _ = storage::save(&path, &self.config);
+        }
+    }
+
+    /// Push the tiles, starting the animation if enabled. Returns whether anything moved
+    fn play(&mut self, direction: Direction) -> bool {
+        let before = self.game.board().clone();
+        let Some(result) = self.game.push(direction) else {
+            return false;
+        };
+        self.animation = (self.config.animation_speed != AnimationSpeed::Off).then(|| Animation {
+            slides: animation::slides(&before, &result),
+            merges: animation::merges(&result),
+            spawned: (result.spawned_row, result.spawned_col),
+            start: Instant::now(),
+        });
+        true
+    }
+
+    /// Push the tiles and redraw continuously while the animation it started runs
+    fn push(&mut self, direction: Direction) -> EventResult {
+        let previous = self.animation.as_ref().map(|animation| animation.start);
+        let result = self.push_tiles(direction);
+        let start = match &self.animation {
+            Some(animation) if previous != Some(animation.start) => animation.start,
+            _ => return result,
+        };
+        let duration = self.config.animation_speed.slide_duration() * 2;
+        result.and(EventResult::with_cb(move |s| {
+            s.set_autorefresh(true);
+            let sink = s.cb_sink().clone();
+            thread::spawn(move || {
+                thread::sleep(duration);
+                let _ = sink.send(Box::new(move |s| {
+                    let current = s.call_on_name("container", |view: &mut Container| {
+                        view.animation.as_ref().map(|animation| animation.start)
+                    });
+                    // a later move keeps refreshing until its own animation ends
+                    if !matches!(current, Some(Some(other)) if other != start) {
+                        s.set_autorefresh(false);
+                    }
+                }));
+            });
+        }))
+    }
+
+    fn push_tiles(&mut self, direction: Direction) -> EventResult {
+        let Some(puzzle) = self.puzzle.clone() else {
+            if !self.play(direction) {
+                return EventResult::Ignored;
+            }
+            return self.check_end();
+        };
+        if puzzle.goal.status(&self.game) != PuzzleStatus::InProgress || !self.play(direction) {
+            return EventResult::Ignored;
+        }
+        let status = puzzle.goal.status(&self.game);
+        if status == PuzzleStatus::InProgress {
+            return EventResult::Ignored;
+        }
+        let message = if status == PuzzleStatus::Solved {
+            format!("Puzzle solved in {} moves!", self.game.moves())
+        } else {
+            "Puzzle failed".to_string()
+        };
+        EventResult::with_cb(move |s| {
+            let puzzle = puzzle.clone();
+            s.add_layer(
+                Dialog::text(message.clone())
+                    .title(puzzle.name.clone())
+                    .button("Retry", move |s| {
+                        let puzzle = puzzle.clone();
+                        s.pop_layer();
                         s.call_on_name("container", |view: &mut Container| {
-                            view.next_history = v;
+                            view.start_puzzle(puzzle)
                         });
-                    }),
+                    })
+                    .dismiss_button("Close"),
             )
-            .title(format!("History: {}", game_history))
-            .with_name("history"),
-        );
-        Container {
-            game,
-            sliders,
-            next_width: backend::DEFAULT_SIZE,
-            next_height: backend::DEFAULT_SIZE,
-            next_history: 1,
+        })
+    }
+
+    /// Show the win dialog the first time [`WIN_TILE`] is reached, and the game over one
+    fn check_end(&mut self) -> EventResult {
+        let score = self.game.score();
+        if self.game.is_over() {
+            let can_undo = !self.game.history().is_empty();
+            return EventResult::with_cb(move |s| {
+                let mut dialog = Dialog::text(format!("No more moves. Final score: {}", score))
+                    .title("Game over")
+                    .button("New game", |s| {
+                        s.pop_layer();
+                        s.call_on_name("container", Container::new_game);
+                    });
+                if can_undo {
+                    dialog.add_button("Undo", |s| {
+                        s.pop_layer();
+                        s.call_on_name("container", |view: &mut Container| view.game.undo());
+                    });
+                }
+                s.add_layer(dialog.dismiss_button("Close"));
+            });
         }
+        if self.won || self.game.max_tile() < WIN_TILE {
+            return EventResult::Ignored;
+        }
+        self.won = true;
+        EventResult::with_cb(move |s| {
+            s.add_layer(
+                Dialog::text(format!(
+                    "You made a {} with a score of {}!",
+                    WIN_TILE, score
+                ))
+                .title("You win")
+                .dismiss_button("Keep going")
+                .button("New game", |s| {
+                    s.pop_layer();
+                    s.call_on_name("container", Container::new_game);
+                }),
+            )
+        })
     }
 
     fn draw_grid(&self, printer: &Printer) {
@@ -141,13 +823,27 @@ impl Container {
     }
 
     fn draw_cell(&self, i: usize, j: usize, printer: &Printer) {
-        let value = self.game.get(i, j);
-        let color = COLORS[value.checked_ilog2().unwrap_or(0).min(12) as usize];
+        self.draw_tile(i as f64, j as f64, self.game.get(i, j), false, printer);
+    }
+
+    /// Draw a tile at a fractional row and column, possibly between two cells.
+    /// `highlight` uses the text color as background
+    fn draw_tile(&self, i: f64, j: f64, value: u64, highlight: bool, printer: &Printer) {
+        let (background, foreground) = (
+            rgb(self.palette.background(value)),
+            rgb(self.palette.foreground(value)),
+        );
+        let color = if highlight {
+            ColorStyle::new(background, foreground)
+        } else {
+            ColorStyle::new(foreground, background)
+        };
+        let (x, y) = (
+            (j * (CELL_EXT_WIDTH - 1) as f64).round() as usize + 1,
+            (i * (CELL_EXT_HEIGHT - 1) as f64).round() as usize + 1,
+        );
         for line in 0..5 {
-            let coord = (
-                j * (CELL_EXT_WIDTH - 1) + 1,
-                i * (CELL_EXT_HEIGHT - 1) + line + 1,
-            );
+            let coord = (x, y + line);
             if value == 0 {
                 printer.print(coord, "         ");
             } else {
@@ -155,11 +851,7 @@ impl Container {
                     printer.print(
                         coord,
                         &if line == 2 && value != 0 {
-                            format!(
-                                "{:^width$}",
-                                self.game.get(i, j),
-                                width = CELL_EXT_WIDTH - 2
-                            )
+                            format!("{:^width$}", value, width = CELL_EXT_WIDTH - 2)
                         } else {
                             " ".repeat(CELL_EXT_WIDTH - 2)
                         },
@@ -171,50 +863,88 @@ impl Container {
 
     fn draw_board(&self, printer: &Printer) {
         self.draw_grid(printer);
-        for i in 0..self.game.height() {
-            for j in 0..self.game.width() {
-                self.draw_cell(i, j, printer);
+        let slide = self.config.animation_speed.slide_duration();
+        let elapsed = self.animation.as_ref().map(|a| a.start.elapsed());
+        match &self.animation {
+            Some(animation) if elapsed < Some(slide) => {
+                let progress = elapsed.unwrap().as_secs_f64() / slide.as_secs_f64();
+                for slide in &animation.slides {
+                    let (i, j) = slide.position(progress);
+                    self.draw_tile(i, j, slide.value, false, printer);
+                }
+            }
+            // merged tiles stay highlighted as long as the slide took
+            Some(animation) if elapsed < Some(slide * 2) => {
+                for i in 0..self.game.height() {
+                    for j in 0..self.game.width() {
+                        if (i, j) != animation.spawned {
+                            let highlight = animation.merges.contains(&(i, j));
+                            let (fi, fj) = (i as f64, j as f64);
+                            self.draw_tile(fi, fj, self.game.get(i, j), highlight, printer);
+                        }
+                    }
+                }
+            }
+            _ => {
+                for i in 0..self.game.height() {
+                    for j in 0..self.game.width() {
+                        self.draw_cell(i, j, printer);
+                    }
+                }
             }
         }
     }
 
     fn draw_history(&self, printer: &Printer) {
-        let history = self
-            .game
-            .history()
-            .iter()
-            .rev()
-            .map(|h| format!("{:?}", h))
-            .collect::<Vec<_>>()
-            .join("\n");
-        let mut dialog = Dialog::around(TextView::new(if history.is_empty() {
-            "no history"
-        } else {
-            &history
-        }))
-        .title("History")
-        .fixed_size((HISTORY_WIDTH, HISTORY_HEIGHT));
-        dialog.layout(printer.offset);
-        dialog.draw(printer);
+        self.history.draw(
+            &printer
+                .cropped((HISTORY_WIDTH, HISTORY_HEIGHT))
+                .focused(self.history_focus),
+        );
     }
 
     fn draw_score(&self, printer: &Printer) {
         let mut dialog = Dialog::text(self.game.score().to_string())
-            .title("Score")
+            .title(if self.daily.is_some() {
+                "Daily"
+            } else if self.puzzle.is_some() {
+                "Puzzle"
+            } else {
+                "Score"
+            })
             .fixed_size((SCORE_WIDTH, SCORE_HEIGHT));
         dialog.layout(printer.offset);
         dialog.draw(printer);
     }
 
-    fn draw_sliders(&self, printer: &Printer) {
-        self.sliders.draw(printer);
+    fn instructions(&self) -> String {
+        let mut text = format!("{} to move", self.config.keymap.describe_moves());
+        for (action, description) in INSTRUCTIONS {
+            text.push_str(&format!(
+                "\n{} to {}",
+                self.config.keymap.describe(action),
+                description
+            ));
+        }
+        text.push_str("\nTab to browse the history");
+        text.push_str("\nEsc to open the menu");
+        text
     }
 
     fn draw_instructions(&self, printer: &Printer) {
-        let mut dialog =
-            Dialog::text("wasd or arrow keys to move\nn to start a new game\nz to undo\nq to quit")
-                .title("Instructions")
-                .resized(SizeConstraint::Free, SizeConstraint::Fixed(6));
+        let mut text = self.instructions();
+        if let Some(puzzle) = &self.puzzle {
+            text.push_str(&format!(
+                "\n\n{}\n{}\nmoves: {}",
+                puzzle.name,
+                puzzle.goal,
+                self.game.moves()
+            ));
+        }
+        let height = text.lines().count() + 2;
+        let mut dialog = Dialog::text(text)
+            .title("Instructions")
+            .resized(SizeConstraint::Free, SizeConstraint::Fixed(height));
         dialog.layout(printer.offset);
         dialog.draw(printer);
     }
@@ -227,56 +957,119 @@ impl Container {
         self.game.height() * (CELL_EXT_HEIGHT - 1) + 1
     }
 
-    fn offsets(&self) -> (Vec2, Vec2, Vec2, Vec2, Vec2) {
+    fn offsets(&self) -> (Vec2, Vec2, Vec2, Vec2) {
         let (x, y) = (OUTER_SPACE, OUTER_SPACE);
         let board_offset = Vec2::new(x, y);
         let history_offset = Vec2::new(board_offset.x + self.width() + INTER_SPACE, y);
         let score_offset = Vec2::new(history_offset.x + HISTORY_WIDTH + INTER_SPACE, y);
-        let sliders_offset = Vec2::new(score_offset.x, score_offset.y + SCORE_HEIGHT);
-        let instructions_offset = Vec2::new(
-            history_offset.x,
-            sliders_offset.y + HISTORY_HEIGHT + INTER_SPACE,
-        );
+        let instructions_offset =
+            Vec2::new(history_offset.x, history_offset.y + HISTORY_HEIGHT + 1);
         (
             board_offset,
             history_offset,
             score_offset,
-            sliders_offset,
             instructions_offset,
         )
     }
+
+    /// Do what `action` is bound to, from a key or the menu
+    pub(crate) fn perform(&mut self, action: Action) -> EventResult {
+        if let Some(direction) = action.direction() {
+            return self.push(direction);
+        }
+        match action {
+            Action::NewGame => {
+                return self.confirm_discard(|view| {
+                    view.new_game();
+                    EventResult::Consumed(None)
+                })
+            }
+            Action::Daily => {
+                return self.confirm_discard(|view| {
+                    view.start_daily();
+                    EventResult::Consumed(None)
+                })
+            }
+            Action::Puzzles => return self.select_puzzle(),
+            Action::HighScores => return self.show_highscores(),
+            Action::Stats => return self.show_stats(),
+            Action::Animations => {
+                self.config.animation_speed = match self.config.animation_speed {
+                    AnimationSpeed::Off => AnimationSpeed::Normal,
+                    _ => AnimationSpeed::Off,
+                };
+            }
+            Action::Settings => return self.show_settings(),
+            Action::Save => return self.save_game(),
+            Action::Undo => {
+                self.animation = None;
+                self.game.undo();
+            }
+            Action::Quit => {
+                return EventResult::with_cb(|s| {
+                    let errors = s.call_on_name("container", |view: &mut Container| {
+                        view.finish();
+                        view.errors.drain(..).collect::<Vec<_>>()
+                    });
+                    match errors {
+                        Some(errors) if !errors.is_empty() => s.add_layer(
+                            Dialog::text(errors.join("\n"))
+                                .title("Error")
+                                .button("Quit", |s| s.quit()),
+                        ),
+                        _ => s.quit(),
+                    }
+                })
+            }
+            _ => {}
+        };
+        EventResult::Consumed(None)
+    }
+
+    /// Do what a menu entry that isn't bound to a key asks for
+    pub(crate) fn perform_menu(&mut self, entry: MenuEntry) -> EventResult {
+        match entry {
+            MenuEntry::Load => self.load_game(),
+            MenuEntry::Replay => self.confirm_discard(|view| {
+                view.replay();
+                EventResult::Consumed(None)
+            }),
+            MenuEntry::Size => self.show_size(),
+            MenuEntry::History => self.show_history(),
+            MenuEntry::Keys => self.show_help(),
+            MenuEntry::JumpBack => self.show_jump_back(),
+        }
+    }
 }
 
 impl View for Container {
     fn draw(&self, printer: &Printer) {
-        let (board_offset, history_offset, score_offset, sliders_offset, instructions_offset) =
-            self.offsets();
+        let (board_offset, history_offset, score_offset, instructions_offset) = self.offsets();
 
         let board_printer = printer.offset(board_offset);
         let history_printer = printer.offset(history_offset);
         let score_printer = printer.offset(score_offset);
-        let sliders_printer = printer.offset(sliders_offset);
         let instructions_printer = printer.offset(instructions_offset);
 
         Container::draw_board(self, &board_printer);
         Container::draw_history(self, &history_printer);
         Container::draw_score(self, &score_printer);
-        Container::draw_sliders(self, &sliders_printer);
         Container::draw_instructions(self, &instructions_printer);
     }
 
-    fn layout(&mut self, constraints: Vec2) {
-        self.sliders.layout(constraints);
+    fn layout(&mut self, _size: Vec2) {
+        self.refresh_history();
+        self.history.layout((HISTORY_WIDTH, HISTORY_HEIGHT).into());
     }
 
-    fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+    fn required_size(&mut self, _constraint: Vec2) -> Vec2 {
         (
             OUTER_SPACE
                 + self.width()
                 + INTER_SPACE
                 + HISTORY_WIDTH
                 + INTER_SPACE
-                + SCORE_WIDTH.max(self.sliders.required_size(constraint).x)
+                + SCORE_WIDTH
                 + OUTER_SPACE,
             (OUTER_SPACE + self.height() + OUTER_SPACE).max(10),
         )
@@ -284,35 +1077,88 @@ impl View for Container {
     }
 
     fn on_event(&mut self, event: Event) -> EventResult {
-        match event {
-            Event::Char('w') | Event::Key(Key::Up) => {
-                self.game.push(Direction::U);
-            }
-            Event::Char('a') | Event::Key(Key::Left) => {
-                self.game.push(Direction::L);
-            }
-            Event::Char('s') | Event::Key(Key::Down) => {
-                self.game.push(Direction::D);
-            }
-            Event::Char('d') | Event::Key(Key::Right) => {
-                self.game.push(Direction::R);
+        if self.history_focus {
+            match event {
+                Event::Key(Key::Tab | Key::Esc) => {
+                    self.history_focus = false;
+                    return EventResult::consumed();
+                }
+                // going back to a move returns to the game
+                Event::Key(Key::Enter) => {
+                    self.history_focus = false;
+                    return self.history.on_event(event);
+                }
+                event @ (Event::Key(_) | Event::Mouse { .. }) => {
+                    return self.history.on_event(event.relativized(self.offsets().1));
+                }
+                // other keys play as usual
+                _ => self.history_focus = false,
             }
-            Event::Char('n') => {
-                self.game = Game::new(self.next_width, self.next_height, self.next_history)
-                    .unwrap_or_default();
-            }
-            Event::Char('z') => {
-                self.game.undo();
+        }
+        let key = match event {
+            Event::Key(Key::Tab) => {
+                self.history_focus = true;
+                return EventResult::consumed();
             }
+            Event::Char(c) => keymap::Key::Char(c),
+            Event::Key(Key::Up) => keymap::Key::Up,
+            Event::Key(Key::Left) => keymap::Key::Left,
+            Event::Key(Key::Down) => keymap::Key::Down,
+            Event::Key(Key::Right) => keymap::Key::Right,
             event @ Event::Mouse { .. } => {
-                return self.sliders.on_event(event.relativized(self.offsets().3))
+                return self.history.on_event(event.relativized(self.offsets().1))
             }
-            _ => {}
+            _ => return EventResult::Ignored,
+        };
+        let result = match self.config.keymap.action(key) {
+            Some(action) => self.perform(action),
+            None => EventResult::Ignored,
         };
-        EventResult::Ignored
+        self.report_errors(result)
     }
+}
+
+/// Menu entries that aren't bound to a key
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum MenuEntry {
+    Load,
+    Replay,
+    Size,
+    History,
+    Keys,
+    JumpBack,
+}
+
+/// Run `f` on the container and process its result, from a menu or a dialog
+pub(crate) fn with_container(s: &mut Cursive, f: impl FnOnce(&mut Container) -> EventResult) {
+    if let Some(result) = s.call_on_name("container", |view: &mut Container| {
+        let result = f(view);
+        view.report_errors(result)
+    }) {
+        result.process(s);
+    }
+}
+
+/// Use the colors of `palette` for the whole interface, not only the tiles
+pub(crate) fn apply_theme(s: &mut Cursive, palette: &Palette) {
+    let mut theme = cursive_theme::Theme::retro();
+    theme.shadow = false;
+    theme.palette[PaletteColor::Background] = rgb(palette.background);
+    theme.palette[PaletteColor::View] = rgb(palette.background);
+    theme.palette[PaletteColor::Primary] = rgb(palette.text);
+    theme.palette[PaletteColor::TitlePrimary] = rgb(palette.text);
+    s.set_theme(theme);
+}
 
-    fn call_on_any(&mut self, selector: &Selector, cb: AnyCb) {
-        self.sliders.call_on_any(selector, cb);
+fn direction_symbol(direction: Direction) -> &'static str {
+    match direction {
+        Direction::U => "\u{2191}",
+        Direction::R => "\u{2192}",
+        Direction::L => "\u{2190}",
+        Direction::D => "\u{2193}",
     }
 }
+
+fn rgb((r, g, b): Rgb) -> Color {
+    Color::Rgb(r, g, b)
+}
@@ -1,14 +1,53 @@
+use std::{env, fs, process};
+
+use backend::{
+    config::{Config, CONFIG_FILE},
+    palette::Theme,
+    puzzle::PuzzlePack,
+    storage,
+};
 use cursive::{traits::Nameable, view::Selector};
 
-use crate::container::Container;
+use crate::container::{apply_theme, Container};
 
 mod container;
+mod menu;
 
 fn main() {
+    let puzzles = match env::args().nth(1) {
+        Some(path) => fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| PuzzlePack::from_json(&json).map_err(|e| e.to_string()))
+            .unwrap_or_else(|error| {
+                eprintln!("Could not load puzzles from {}: {}", path, error);
+                process::exit(1)
+            }),
+        None => PuzzlePack::builtin(),
+    };
+    let config: Config = match storage::config_file(CONFIG_FILE) {
+        Some(path) => storage::load(&path).unwrap_or_else(|error| {
+            eprintln!(
+                "Could not load configuration from {}: {}",
+                path.display(),
+                error
+            );
+            process::exit(1)
+        }),
+        None => Config::default(),
+    };
+
+    let palette = config.theme.palette().unwrap_or_else(|error| {
+        eprintln!("Could not load theme {}: {}", config.theme, error);
+        Theme::Classic
+            .builtin_palette()
+            .expect("the classic theme is built in")
+    });
+
     let mut siv = cursive::default();
-    siv.add_global_callback('q', |s| s.quit());
+    apply_theme(&mut siv, &palette);
+    menu::install(&mut siv);
 
-    let container = Container::new().with_name("container");
+    let container = Container::new(puzzles, config, palette).with_name("container");
     siv.add_layer(container);
     siv.focus(&Selector::Name("container")).unwrap();
 
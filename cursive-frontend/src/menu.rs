@@ -0,0 +1,60 @@
+use backend::keymap::Action;
+use cursive::{event::Key, menu::Tree, views::Dialog, Cursive};
+
+use crate::container::{with_container, Container, MenuEntry};
+
+/// Add the menubar, opened with Esc
+pub(crate) fn install(siv: &mut Cursive) {
+    siv.menubar()
+        .add_subtree(
+            "Game",
+            Tree::new()
+                .leaf("New", action(Action::NewGame))
+                .leaf("Daily challenge", action(Action::Daily))
+                .leaf("Puzzles", action(Action::Puzzles))
+                .delimiter()
+                .leaf("Save", action(Action::Save))
+                .leaf("Load", entry(MenuEntry::Load))
+                .leaf("Replay", entry(MenuEntry::Replay))
+                .leaf("Undo", action(Action::Undo))
+                .leaf("Go back to move", entry(MenuEntry::JumpBack))
+                .delimiter()
+                .leaf("High scores", action(Action::HighScores))
+                .leaf("Statistics", action(Action::Stats))
+                .delimiter()
+                .leaf("Quit", action(Action::Quit)),
+        )
+        .add_subtree(
+            "Options",
+            Tree::new()
+                .leaf("Size", entry(MenuEntry::Size))
+                .leaf("History", entry(MenuEntry::History))
+                .leaf("Theme, animations and keys", action(Action::Settings)),
+        )
+        .add_subtree(
+            "Help",
+            Tree::new()
+                .leaf("Keys", entry(MenuEntry::Keys))
+                .leaf("About", |s| {
+                    s.add_layer(Dialog::info(format!(
+                        "2048 {}\n\nSlide the tiles to merge equal ones,\nuntil you make a 2048",
+                        env!("CARGO_PKG_VERSION")
+                    )))
+                }),
+        );
+    siv.set_autohide_menu(false);
+    // only from the game itself, dialogs on top of it keep the focus
+    siv.add_global_callback(Key::Esc, |s| {
+        if s.screen().len() == 1 {
+            s.select_menubar();
+        }
+    });
+}
+
+fn action(action: Action) -> impl Fn(&mut Cursive) {
+    move |s| with_container(s, |view: &mut Container| view.perform(action))
+}
+
+fn entry(entry: MenuEntry) -> impl Fn(&mut Cursive) {
+    move |s| with_container(s, |view: &mut Container| view.perform_menu(entry))
+}
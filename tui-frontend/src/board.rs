@@ -0,0 +1,361 @@
+use std::{
+    io::{stdout, Result as IOResult, Write},
+    thread::sleep,
+    time::Duration,
+};
+
+use backend::{
+    animation::{self, AnimationSpeed},
+    keymap::{Action, Keymap},
+    palette::{Palette, Rgb, Theme},
+    Direction, Game, PushResult,
+};
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType},
+};
+
+/// Actions explained in the panel, after the moves
+const INSTRUCTIONS: [(Action, &str); 3] = [
+    (Action::Undo, "undo"),
+    (Action::Save, "save"),
+    (Action::Quit, "quit"),
+];
+/// Lines of the panel: moves and instructions, a blank line, status, score, moves and history
+const PANEL_HEIGHT: usize = INSTRUCTIONS.len() + 6;
+/// Width of the panel when it is beside the board
+const PANEL_WIDTH: usize = 26;
+/// Columns between the board and the panel beside it
+const PANEL_GAP: usize = 2;
+const MAX_CELL_WIDTH: usize = 14;
+const MAX_CELL_HEIGHT: usize = 5;
+const FRAME_DURATION: Duration = Duration::from_millis(16);
+
+/// Size of the inside of a cell, borders excluded
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct CellSize {
+    width: usize,
+    height: usize,
+}
+
+impl CellSize {
+    /// Biggest cells that let the board fit in `columns` x `rows`,
+    /// but never too narrow for the largest tile
+    fn fit(game: &Game, columns: usize, rows: usize) -> Self {
+        let digits = game.max_tile().max(1).ilog10() as usize + 1;
+        let width = (columns.saturating_sub(1) / game.width())
+            .saturating_sub(1)
+            .clamp(digits + 2, MAX_CELL_WIDTH.max(digits + 2));
+        let height = (rows.saturating_sub(1) / game.height())
+            .saturating_sub(1)
+            .clamp(1, MAX_CELL_HEIGHT);
+        Self { width, height }
+    }
+
+    fn board_width(&self, game: &Game) -> usize {
+        game.width() * (self.width + 1) + 1
+    }
+}
+
+/// Where everything goes on the terminal
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct Layout {
+    terminal: (usize, usize),
+    size: (usize, usize),
+    cell: CellSize,
+    board: (usize, usize),
+    panel: (usize, usize),
+    panel_width: usize,
+}
+
+impl Layout {
+    /// Put the panel beside the board if both fit, otherwise above it
+    fn new(game: &Game) -> Self {
+        let (columns, rows) = terminal::size().unwrap_or((80, 24));
+        let (columns, rows) = (columns as usize, rows as usize);
+        let size = (game.height(), game.width());
+        let beside = CellSize::fit(game, columns.saturating_sub(PANEL_WIDTH + PANEL_GAP), rows);
+        if beside.board_width(game) + PANEL_GAP + PANEL_WIDTH <= columns {
+            Self {
+                terminal: (columns, rows),
+                size,
+                cell: beside,
+                board: (0, 0),
+                panel: (beside.board_width(game) + PANEL_GAP, 0),
+                panel_width: PANEL_WIDTH,
+            }
+        } else {
+            Self {
+                terminal: (columns, rows),
+                size,
+                cell: CellSize::fit(game, columns, rows.saturating_sub(PANEL_HEIGHT + 1)),
+                board: (0, PANEL_HEIGHT + 1),
+                panel: (0, 0),
+                panel_width: columns,
+            }
+        }
+    }
+}
+
+/// Keeps what is currently on the terminal, so that only what changed gets redrawn
+pub(crate) struct Screen {
+    keymap: Keymap,
+    palette: Palette,
+    slide_frames: u32,
+    layout: Option<Layout>,
+    board: Vec<Vec<u64>>,
+    panel: Vec<String>,
+}
+
+impl Screen {
+    /// `keymap` is only used to explain the keys
+    pub(crate) fn new(keymap: Keymap, speed: AnimationSpeed) -> Self {
+        Self {
+            keymap,
+            palette: Theme::Classic
+                .builtin_palette()
+                .expect("the classic theme is built in"),
+            slide_frames: (speed.slide_duration().as_millis() / FRAME_DURATION.as_millis()).max(1)
+                as u32,
+            layout: None,
+            board: vec![],
+            panel: vec![],
+        }
+    }
+
+    /// Forget what is on the terminal, so that the next draw repaints everything.
+    /// Needed after the terminal is resized
+    pub(crate) fn invalidate(&mut self) {
+        self.layout = None;
+    }
+
+    /// Draw the panel, with `status` in it, and the board, touching only what changed
+    pub(crate) fn draw(&mut self, game: &Game, status: &str) -> IOResult<()> {
+        let mut stdout = stdout();
+        let layout = Layout::new(game);
+        if self.layout != Some(layout) {
+            queue!(stdout, Clear(ClearType::All))?;
+            draw_grid(&mut stdout, game, &layout)?;
+            self.layout = Some(layout);
+            self.board = vec![vec![u64::MAX; game.width()]; game.height()];
+            self.panel = vec![String::new(); PANEL_HEIGHT];
+        }
+        for (i, line) in panel(&self.keymap, game, status, layout.panel_width)
+            .into_iter()
+            .enumerate()
+        {
+            if self.panel[i] != line {
+                let clear = self.panel[i]
+                    .chars()
+                    .count()
+                    .saturating_sub(line.chars().count());
+                queue!(
+                    stdout,
+                    MoveTo(layout.panel.0 as u16, (layout.panel.1 + i) as u16),
+                    Print(&line),
+                    Print(" ".repeat(clear))
+                )?;
+                self.panel[i] = line;
+            }
+        }
+        for i in 0..game.height() {
+            for j in 0..game.width() {
+                if self.board[i][j] != game.get(i, j) {
+                    draw_cell(&mut stdout, &self.palette, &layout, i, j, game.get(i, j))?;
+                    self.board[i][j] = game.get(i, j);
+                }
+            }
+        }
+        stdout.flush()
+    }
+
+    /// Slide the tiles of `before` as described by `result`, flash the merged tiles,
+    /// then flash the spawned one and draw `game` as usual
+    pub(crate) fn animate(
+        &mut self,
+        before: &[Vec<u64>],
+        result: &PushResult,
+        game: &Game,
+        status: &str,
+    ) -> IOResult<()> {
+        let Some(layout) = self
+            .layout
+            .filter(|layout| layout.size == (before.len(), before[0].len()))
+        else {
+            return self.draw(game, status);
+        };
+        let mut stdout = stdout();
+        let slides = animation::slides(before, result);
+        for frame in 1..=self.slide_frames {
+            let progress = frame as f64 / self.slide_frames as f64;
+            draw_grid(&mut stdout, game, &layout)?;
+            for i in 0..game.height() {
+                for j in 0..game.width() {
+                    draw_cell(&mut stdout, &self.palette, &layout, i, j, 0)?;
+                }
+            }
+            for slide in &slides {
+                let (i, j) = slide.position(progress);
+                draw_tile(&mut stdout, &self.palette, &layout, i, j, slide.value, false)?;
+            }
+            stdout.flush()?;
+            sleep(FRAME_DURATION);
+        }
+        for (i, j) in animation::merges(result) {
+            draw_tile(
+                &mut stdout,
+                &self.palette,
+                &layout,
+                i as f64,
+                j as f64,
+                game.get(i, j),
+                true,
+            )?;
+        }
+        stdout.flush()?;
+        sleep(FRAME_DURATION * 3);
+        draw_tile(
+            &mut stdout,
+            &self.palette,
+            &layout,
+            result.spawned_row as f64,
+            result.spawned_col as f64,
+            result.spawned_value,
+            true,
+        )?;
+        stdout.flush()?;
+        sleep(FRAME_DURATION * 3);
+        self.board = vec![vec![u64::MAX; game.width()]; game.height()];
+        self.draw(game, status)
+    }
+}
+
+fn panel(keymap: &Keymap, game: &Game, status: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![format!("{} to move", keymap.describe_moves())];
+    lines.extend(
+        INSTRUCTIONS
+            .iter()
+            .map(|&(action, text)| format!("{} to {}", keymap.describe(action), text)),
+    );
+    lines.push(String::new());
+    lines.push(status.to_string());
+    lines.push(format!("SCORE: {}", game.score()));
+    lines.push(format!("MOVES: {}", game.moves()));
+    let history = game.history();
+    let shown = (width.saturating_sub(9) / 2).min(history.len());
+    lines.push(format!(
+        "HISTORY: {}",
+        history[history.len() - shown..]
+            .iter()
+            .map(direction_symbol)
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+    lines
+        .into_iter()
+        .map(|line| line.chars().take(width).collect())
+        .collect()
+}
+
+fn direction_symbol(direction: &Direction) -> &'static str {
+    match direction {
+        Direction::U => "\u{2191}",
+        Direction::R => "\u{2192}",
+        Direction::L => "\u{2190}",
+        Direction::D => "\u{2193}",
+    }
+}
+
+fn draw_grid(out: &mut impl Write, game: &Game, layout: &Layout) -> IOResult<()> {
+    let cell = layout.cell;
+    let border = |left: char, middle: char, right: char| {
+        let mut line = String::new();
+        line.push(left);
+        for j in 0..game.width() {
+            line.push_str(&"\u{2500}".repeat(cell.width));
+            line.push(if j + 1 == game.width() { right } else { middle });
+        }
+        line
+    };
+    let (x, top) = layout.board;
+    let mut separator = String::new();
+    for _ in 0..game.width() {
+        separator.push('\u{2502}');
+        separator.push_str(&" ".repeat(cell.width));
+    }
+    separator.push('\u{2502}');
+    queue!(
+        out,
+        MoveTo(x as u16, top as u16),
+        Print(border('\u{250c}', '\u{252c}', '\u{2510}'))
+    )?;
+    for i in 0..game.height() {
+        let y = top + 1 + i * (cell.height + 1);
+        for line in 0..cell.height {
+            queue!(out, MoveTo(x as u16, (y + line) as u16), Print(&separator))?;
+        }
+        let (left, middle, right) = if i + 1 == game.height() {
+            ('\u{2514}', '\u{2534}', '\u{2518}')
+        } else {
+            ('\u{251c}', '\u{253c}', '\u{2524}')
+        };
+        queue!(
+            out,
+            MoveTo(x as u16, (y + cell.height) as u16),
+            Print(border(left, middle, right))
+        )?;
+    }
+    Ok(())
+}
+
+/// Fill the inside of a cell, with the value centered
+fn draw_cell(
+    out: &mut impl Write,
+    palette: &Palette,
+    layout: &Layout,
+    i: usize,
+    j: usize,
+    value: u64,
+) -> IOResult<()> {
+    draw_tile(out, palette, layout, i as f64, j as f64, value, false)
+}
+
+/// Draw a tile as big as a cell at a fractional row and column, possibly between two cells.
+/// `highlight` swaps background and text colors
+fn draw_tile(
+    out: &mut impl Write,
+    palette: &Palette,
+    layout: &Layout,
+    i: f64,
+    j: f64,
+    value: u64,
+    highlight: bool,
+) -> IOResult<()> {
+    let cell = layout.cell;
+    let x = layout.board.0 + 1 + (j * (cell.width + 1) as f64).round() as usize;
+    let y = layout.board.1 + 1 + (i * (cell.height + 1) as f64).round() as usize;
+    let (mut background, mut foreground) = (palette.background(value), palette.foreground(value));
+    if highlight {
+        (background, foreground) = (foreground, background);
+    }
+    queue!(
+        out,
+        SetBackgroundColor(rgb(background)),
+        SetForegroundColor(rgb(foreground))
+    )?;
+    for line in 0..cell.height {
+        let text = if value != 0 && line == (cell.height - 1) / 2 {
+            format!("{:^width$}", value, width = cell.width)
+        } else {
+            " ".repeat(cell.width)
+        };
+        queue!(out, MoveTo(x as u16, (y + line) as u16), Print(text))?;
+    }
+    queue!(out, ResetColor)
+}
+
+fn rgb((r, g, b): Rgb) -> Color {
+    Color::Rgb { r, g, b }
+}
@@ -1,75 +1,231 @@
 use std::{
-    io,
+    fs,
     io::{stdout, Result as IOResult},
-    num::ParseIntError,
+    path::PathBuf,
     time::Duration,
 };
 
-use backend::{Direction, Game};
+use backend::{
+    animation::AnimationSpeed,
+    config::{Config, CONFIG_FILE},
+    daily::{DailyChallenge, DailyResults, Date, DAILY_FILE},
+    highscores::{HighScores, HIGHSCORES_FILE},
+    keymap::{Action, Key},
+    stats::{Statistics, STATS_FILE},
+    storage, Game, MAX_SIZE, MIN_SIZE,
+};
+use clap::{error::ErrorKind, CommandFactory, Parser};
 use crossterm::{
-    cursor::{Hide, MoveTo, Show},
+    cursor::{Hide, Show},
     event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind},
     execute,
-    style::Print,
-    terminal::{
-        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
-        LeaveAlternateScreen,
-    },
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-fn main() -> IOResult<()> {
-    let mut buffer = String::new();
-    println!("Insert grid height (min 3 max 10)");
-    io::stdin().read_line(&mut buffer).unwrap();
-    let height: usize = buffer.trim_end().parse().unwrap();
-
-    buffer.clear();
-    println!("Insert grid width (min 3 max 10)");
-    io::stdin().read_line(&mut buffer).unwrap();
-    let width: usize = buffer.trim_end().parse().unwrap();
-
-    buffer.clear();
-    println!("Insert max history length (press Enter for no history)");
-    io::stdin().read_line(&mut buffer).unwrap();
-    let max_history: usize = buffer.trim_end().parse().unwrap_or(0);
-
-    buffer.clear();
-    println!("Insert seed (optional, press Enter for random)");
-    io::stdin().read_line(&mut buffer).unwrap();
-    let seed: Result<u64, ParseIntError> = buffer.trim_end().parse();
-
-    let mut game = match seed {
-        Ok(seed) => Game::from_seed(height, width, max_history, seed),
-        Err(_) => Game::new(height, width, max_history),
+use crate::{board::Screen, settings::Settings};
+
+mod board;
+mod settings;
+
+/// Play 2048 in the terminal.
+/// Without options, a settings screen lets you choose how to play.
+/// Options override the configuration file for this game only
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Args {
+    /// Grid height
+    #[arg(long, value_parser = parse_size)]
+    height: Option<usize>,
+    /// Grid width
+    #[arg(long, value_parser = parse_size)]
+    width: Option<usize>,
+    /// Max history length, 0 disables undo
+    #[arg(long)]
+    history: Option<usize>,
+    /// Seed of the game, random if missing
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Play today's daily challenge
+    #[arg(long, conflicts_with_all = ["height", "width", "history", "seed"])]
+    daily: bool,
+    /// Resume a game saved with V. The game is saved back to the same file
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["height", "width", "history", "seed", "daily"])]
+    load: Option<PathBuf>,
+    /// Move tiles instantly instead of sliding them
+    #[arg(long)]
+    no_animations: bool,
+    /// Configuration file to use instead of the one in the configuration directory
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+impl Args {
+    fn is_empty(&self) -> bool {
+        self.height.is_none()
+            && self.width.is_none()
+            && self.history.is_none()
+            && self.seed.is_none()
+            && !self.daily
+            && self.load.is_none()
     }
-    .expect("Invalid width or height");
 
-    buffer.clear();
-    buffer.reserve(10 + 2 * game.width() * game.height());
+    fn settings(&self, mut config: Config) -> Settings {
+        config.height = self.height.unwrap_or(config.height);
+        config.width = self.width.unwrap_or(config.width);
+        config.history = self.history.unwrap_or(config.history);
+        Settings {
+            config,
+            seed: self.seed,
+            daily: self.daily,
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Result<usize, String> {
+    let size: usize = value
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", value))?;
+    if (MIN_SIZE..=MAX_SIZE).contains(&size) {
+        Ok(size)
+    } else {
+        Err(format!("must be between {} and {}", MIN_SIZE, MAX_SIZE))
+    }
+}
+
+fn main() -> IOResult<()> {
+    let args = Args::parse();
+    let loaded = args.load.as_ref().map(|path| {
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| Game::load(&json).map_err(|e| e.to_string()))
+            .unwrap_or_else(|error| {
+                Args::command()
+                    .error(
+                        ErrorKind::ValueValidation,
+                        format!("can't load {}: {}", path.display(), error),
+                    )
+                    .exit()
+            })
+    });
+    let config_path = args
+        .config
+        .clone()
+        .or_else(|| storage::config_file(CONFIG_FILE));
+    let config: Config = match &config_path {
+        Some(path) => storage::load(path).unwrap_or_else(|error| {
+            Args::command()
+                .error(
+                    ErrorKind::ValueValidation,
+                    format!("can't load configuration {}: {}", path.display(), error),
+                )
+                .exit()
+        }),
+        None => Config::default(),
+    };
+    let save_path = args
+        .load
+        .clone()
+        .or_else(|| storage::data_file(storage::SAVE_FILE));
+
     execute!(stdout(), EnterAlternateScreen, Hide)?;
-    display(&game, &mut buffer)?;
     enable_raw_mode()?;
+    let mut status = String::new();
+    let settings = if args.is_empty() {
+        let settings = settings::run(args.settings(config.clone()))?;
+        if let (Some(settings), Some(path)) = (&settings, &config_path) {
+            if settings.config != config {
+                if let Err(error) = storage::save(path, &settings.config) {
+                    status = format!("Could not save configuration: {}", error);
+                }
+            }
+        }
+        settings
+    } else {
+        Some(args.settings(config))
+    };
+    let Some(Settings {
+        mut config,
+        seed,
+        daily,
+    }) = settings
+    else {
+        disable_raw_mode()?;
+        execute!(stdout(), Show, LeaveAlternateScreen)?;
+        return Ok(());
+    };
+    // applied after the settings screen, so that it never reaches the configuration file
+    if args.no_animations {
+        config.animation_speed = AnimationSpeed::Off;
+    }
+    let daily = daily.then(|| DailyChallenge::new(Date::today()));
+    let mut game = match (loaded, daily) {
+        (Some(game), _) => game,
+        (None, Some(daily)) => daily.game(),
+        (None, None) => config.game(seed),
+    };
+    let keymap = config.keymap;
+
+    let mut screen = Screen::new(keymap.clone(), config.animation_speed);
+    screen.draw(&game, &status)?;
 
     loop {
         if poll(Duration::from_millis(1))? {
-            if let Event::Key(KeyEvent {
-                code,
-                kind: KeyEventKind::Press,
-                ..
-            }) = read()?
-            {
-                let moved = match code {
-                    KeyCode::Up | KeyCode::Char('w') => game.push(Direction::U).is_some(),
-                    KeyCode::Left | KeyCode::Char('a') => game.push(Direction::L).is_some(),
-                    KeyCode::Down | KeyCode::Char('s') => game.push(Direction::D).is_some(),
-                    KeyCode::Right | KeyCode::Char('d') => game.push(Direction::R).is_some(),
-                    KeyCode::Char('z') => game.undo(),
-                    KeyCode::Char('q') => break,
-                    _ => false,
-                };
-                if moved {
-                    display(&game, &mut buffer)?;
+            match read()? {
+                Event::Key(KeyEvent {
+                    code,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    let key = match code {
+                        KeyCode::Up => Key::Up,
+                        KeyCode::Left => Key::Left,
+                        KeyCode::Down => Key::Down,
+                        KeyCode::Right => Key::Right,
+                        KeyCode::Char(c) => Key::Char(c),
+                        _ => continue,
+                    };
+                    let Some(action) = keymap.action(key) else {
+                        continue;
+                    };
+                    if let Some(direction) = action.direction() {
+                        let before = game.board().clone();
+                        if let Some(result) = game.push(direction) {
+                            status.clear();
+                            if config.animation_speed != AnimationSpeed::Off {
+                                screen.animate(&before, &result, &game, &status)?;
+                            } else {
+                                screen.draw(&game, &status)?;
+                            }
+                        }
+                        continue;
+                    }
+                    let changed = match action {
+                        Action::Undo => game.undo(),
+                        Action::Save => {
+                            status = match &save_path {
+                                Some(path) => match fs::write(path, game.save()) {
+                                    Ok(()) => {
+                                        game.suspend();
+                                        format!("Saved to {}", path.display())
+                                    }
+                                    Err(error) => format!("Could not save: {}", error),
+                                },
+                                None => "No place to save the game".to_string(),
+                            };
+                            true
+                        }
+                        Action::Quit => break,
+                        _ => false,
+                    };
+                    if changed {
+                        screen.draw(&game, &status)?;
+                    }
                 }
+                Event::Resize(_, _) => {
+                    screen.invalidate();
+                    screen.draw(&game, &status)?;
+                }
+                _ => {}
             }
         }
     }
@@ -77,50 +233,106 @@ fn main() -> IOResult<()> {
     disable_raw_mode()?;
     execute!(stdout(), Show, LeaveAlternateScreen)?;
 
+    // A game left unchanged since it was saved is only suspended, it's recorded once resumed
+    if !game.is_suspended() && !game.is_recorded() {
+        if let Some(daily) = daily {
+            record_daily(daily.date, &game);
+        }
+        record_highscore(&game);
+        record_stats(&game);
+        if let Some(path) = &save_path {
+            if let Err(error) = game.mark_save_recorded(path) {
+                eprintln!(
+                    "Could not mark the saved game in {} as recorded: {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+    }
+
     Ok(())
 }
 
-fn display(game: &Game, buffer: &mut String) -> IOResult<()> {
-    buffer.clear();
-    buffer.push_str(&format!(
-        "WASD or arrows to move\nZ to undo\nQ to quit\n\nSCORE: {}\n\n",
-        game.score()
-    ));
-    let max_width = game
-        .board()
-        .iter()
-        .flatten()
-        .max()
-        .unwrap()
-        .max(&16)
-        .ilog10() as usize
-        + 1;
-    buffer.push(' ');
-    for _ in 0..game.width() {
-        buffer.push_str(&"-".repeat(max_width));
-        buffer.push('+');
+fn record_daily(date: Date, game: &Game) {
+    let Some(path) = storage::data_file(DAILY_FILE) else {
+        return;
+    };
+    let recorded = storage::update(&path, |results: &mut DailyResults| {
+        (results.record(date, game), results.get(date))
+    });
+    match recorded {
+        Ok((true, _)) => println!(
+            "New best for the {} daily challenge: {}",
+            date,
+            game.score()
+        ),
+        Ok((false, Some(best))) => println!(
+            "Daily challenge {}: scored {}, best is {}",
+            date,
+            game.score(),
+            best.score
+        ),
+        Ok((false, None)) => {}
+        Err(error) => eprintln!(
+            "Could not record the daily result in {}, leaving it as is: {}",
+            path.display(),
+            error
+        ),
     }
-    buffer.pop();
-    buffer.push('\n');
-    for row in game.board() {
-        buffer.push('|');
-        for number in row {
-            buffer.push_str(&format!("{: <max_width$}|", number, max_width = max_width));
-        }
-        buffer.push('\n');
-        buffer.push(' ');
-        for _ in 0..game.width() {
-            buffer.push_str(&"-".repeat(max_width));
-            buffer.push('+');
+}
+
+fn record_highscore(game: &Game) {
+    let Some(path) = storage::data_file(HIGHSCORES_FILE) else {
+        return;
+    };
+    let recorded = storage::update(&path, |highscores: &mut HighScores| {
+        let rank = highscores.record(game, Date::today());
+        (rank, highscores.get(game.height(), game.width()).to_vec())
+    });
+    let (rank, scores) = match recorded {
+        Ok(recorded) => recorded,
+        Err(error) => {
+            eprintln!(
+                "Could not record the high score in {}, leaving it as is: {}",
+                path.display(),
+                error
+            );
+            return;
         }
-        buffer.pop();
-        buffer.push('\n');
+    };
+    println!("\nHigh scores {}x{}", game.height(), game.width());
+    println!(
+        "{:>2}  {:>8}  {:>6}  {:>5}  {:>20}  {:<10}",
+        "#", "score", "tile", "moves", "seed", "date"
+    );
+    for (i, score) in scores.iter().enumerate() {
+        println!(
+            "{:>2}  {:>8}  {:>6}  {:>5}  {:>20}  {}{}",
+            i + 1,
+            score.score,
+            score.max_tile,
+            score.moves,
+            score.seed,
+            score.date,
+            if rank == Some(i) {
+                "  <- this game"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+fn record_stats(game: &Game) {
+    let Some(path) = storage::data_file(STATS_FILE) else {
+        return;
+    };
+    if let Err(error) = storage::update(&path, |stats: &mut Statistics| stats.record(game)) {
+        eprintln!(
+            "Could not record the statistics in {}, leaving them as they are: {}",
+            path.display(),
+            error
+        );
     }
-    execute!(
-        stdout(),
-        Clear(ClearType::All),
-        MoveTo(0, 0),
-        Print(&buffer)
-    )?;
-    Ok(())
 }
@@ -0,0 +1,175 @@
+use std::io::{stdout, Result as IOResult};
+
+use backend::{
+    animation::AnimationSpeed,
+    config::{Config, UNLIMITED_HISTORY},
+    keymap::{Keymap, Preset},
+    MAX_SIZE, MIN_SIZE,
+};
+use crossterm::{
+    cursor::MoveTo,
+    event::{read, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute, queue,
+    style::{Print, Stylize},
+    terminal::{Clear, ClearType},
+};
+
+const FIELDS: usize = 8;
+/// Longest history reached with the arrows, one more step is unlimited, then 0
+const MAX_HISTORY_STEP: usize = 100;
+
+/// Options chosen in the settings screen: the configuration, and what only applies to this game
+#[derive(Clone, Debug)]
+pub(crate) struct Settings {
+    pub(crate) config: Config,
+    pub(crate) seed: Option<u64>,
+    pub(crate) daily: bool,
+}
+
+/// Show the settings screen, starting from `settings`,
+/// until the player starts a game, or returns `None` if they quit.
+/// Changing the key preset drops remapped keys.
+/// Expects the terminal to be in raw mode
+pub(crate) fn run(mut settings: Settings) -> IOResult<Option<Settings>> {
+    let mut selected = 0;
+    loop {
+        draw(&settings, selected)?;
+        let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = read()?
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Up | KeyCode::Char('w') => selected = (selected + FIELDS - 1) % FIELDS,
+            KeyCode::Down | KeyCode::Char('s') | KeyCode::Tab => selected = (selected + 1) % FIELDS,
+            KeyCode::Left | KeyCode::Char('a') => settings.change(selected, -1),
+            KeyCode::Right | KeyCode::Char('d') => settings.change(selected, 1),
+            KeyCode::Char(c @ '0'..='9') if selected == 3 => {
+                let digit = c.to_digit(10).unwrap() as u64;
+                settings.seed = Some(
+                    settings
+                        .seed
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|seed| seed.checked_add(digit))
+                        .unwrap_or(u64::MAX),
+                );
+            }
+            KeyCode::Backspace if selected == 3 => {
+                settings.seed = settings
+                    .seed
+                    .map(|seed| seed / 10)
+                    .filter(|&seed| seed != 0);
+            }
+            KeyCode::Enter => return Ok(Some(settings)),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+impl Settings {
+    fn change(&mut self, field: usize, delta: isize) {
+        let clamp = |value: usize| value.saturating_add_signed(delta).clamp(MIN_SIZE, MAX_SIZE);
+        let config = &mut self.config;
+        match field {
+            0 => config.height = clamp(config.height),
+            1 => config.width = clamp(config.width),
+            2 => config.history = step_history(config.history, delta),
+            4 => self.daily = !self.daily,
+            5 => {
+                config.animation_speed = cycle(&AnimationSpeed::ALL, config.animation_speed, delta)
+            }
+            6 => {
+                config.keymap =
+                    Keymap::from_preset(cycle(&Preset::ALL, config.keymap.preset, delta))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// History length after or before `history`, going from [`MAX_HISTORY_STEP`]
+/// or any longer length to unlimited, and wrapping around from unlimited to 0
+fn step_history(history: usize, delta: isize) -> usize {
+    match (history == UNLIMITED_HISTORY, delta > 0) {
+        (true, true) => 0,
+        (true, false) => MAX_HISTORY_STEP,
+        (false, true) if history >= MAX_HISTORY_STEP => UNLIMITED_HISTORY,
+        (false, false) if history == 0 => UNLIMITED_HISTORY,
+        _ => history.saturating_add_signed(delta),
+    }
+}
+
+/// Value after or before `current` in `values`, wrapping around
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, delta: isize) -> T {
+    let index = values
+        .iter()
+        .position(|&value| value == current)
+        .unwrap_or(0);
+    values[(index as isize + delta).rem_euclid(values.len() as isize) as usize]
+}
+
+fn draw(settings: &Settings, selected: usize) -> IOResult<()> {
+    let config = &settings.config;
+    let seed = settings
+        .seed
+        .map_or("random".to_string(), |seed| seed.to_string());
+    let keys = if config.keymap.bindings.is_empty() {
+        config.keymap.preset.to_string()
+    } else {
+        format!("{}, remapped", config.keymap.preset)
+    };
+    let lines = [
+        format!("Height    < {} >", config.height),
+        format!("Width     < {} >", config.width),
+        if config.history == UNLIMITED_HISTORY {
+            "History   < unlimited >".to_string()
+        } else {
+            format!("History   < {} >", config.history)
+        },
+        format!("Seed        {}", seed),
+        format!(
+            "Daily     < {} >",
+            if settings.daily { "yes" } else { "no" }
+        ),
+        format!("Animations< {} >", config.animation_speed),
+        format!("Keys      < {} >", keys),
+        "Start".to_string(),
+    ];
+    let mut stdout = stdout();
+    queue!(
+        stdout,
+        Clear(ClearType::All),
+        MoveTo(0, 0),
+        Print("2048 settings"),
+        MoveTo(0, 1),
+        Print("Up/Down to select, Left/Right to change, digits to type the seed"),
+        MoveTo(0, 2),
+        Print("Everything but the seed and daily is saved for next time"),
+        MoveTo(0, 3),
+        Print(format!(
+            "Enter to start, Q to quit. Size goes from {} to {}",
+            MIN_SIZE, MAX_SIZE
+        )),
+    )?;
+    for (i, line) in lines.iter().enumerate() {
+        queue!(stdout, MoveTo(2, 5 + i as u16))?;
+        if i == selected {
+            queue!(stdout, Print(line.as_str().reverse()))?;
+        } else {
+            queue!(stdout, Print(line))?;
+        }
+    }
+    if settings.daily {
+        queue!(
+            stdout,
+            MoveTo(2, 6 + FIELDS as u16),
+            Print("The daily challenge ignores the other settings")
+        )?;
+    }
+    execute!(stdout)
+}
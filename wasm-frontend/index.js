@@ -1,12 +1,115 @@
-import init, {new_game, push} from "./pkg/wasm_frontend.js";
+import init, {
+    Direction, Game, Status, key_action, load_config, max_size, min_size, rules, slide_millis, swipe_threshold,
+} from "./pkg/wasm_frontend.js";
 
-init().then(initialize_grid);
+const DAILY_RESULTS = "daily-results";
+const CONFIG = "config";
+const GAMES = "games";
+const BEST_SCORE = "best-score";
+// History length of the configuration that keeps every move
+const UNLIMITED_HISTORY = "unlimited";
+const DIRECTIONS = {up: Direction.Up, down: Direction.Down, left: Direction.Left, right: Direction.Right};
+const LETTERS = {U: Direction.Up, L: Direction.Left, D: Direction.Down, R: Direction.Right};
+// Time between two moves of a replay at 1x speed, in milliseconds
+const REPLAY_STEP = 500;
+// Room taken by the cells of the largest side of a board and the gaps between them
+const BOARD_SIZE = 470;
+const GAP = 14;
+
+// Every board of the page, each with its own game. Keys and buttons act on the active one
+let boards = [];
+let active = null;
+let config = null;
+
+init().then(() => {
+    apply_config(localStorage.getItem(CONFIG));
+    for (const id of ["settings-height", "settings-width"]) {
+        const input = document.getElementById(id);
+        input.min = min_size();
+        input.max = max_size();
+    }
+    const hash = location.hash;
+    if (!restore_games() && hash.length <= 1) {
+        add_board();
+    }
+    if (hash.length > 1) {
+        // Restoring the boards rewrote the fragment with the one of the active board
+        history.replaceState(null, "", hash);
+        open_link();
+    }
+    if (active === null) {
+        add_board();
+    }
+    show_best();
+});
+
+// The configuration is the same JSON as the configuration file of the terminal frontends.
+// An invalid stored configuration is left in place, and the defaults used until it's replaced
+function apply_config(json) {
+    try {
+        config = JSON.parse(load_config(json));
+        localStorage.setItem(CONFIG, JSON.stringify(config));
+    } catch (error) {
+        show_error(`The stored configuration is invalid, using the defaults: ${error}`);
+        config = JSON.parse(load_config(null));
+    }
+    document.documentElement.style.setProperty("--slide-duration", `${slide_millis()}ms`);
+}
+
+// Tell the player about a problem, until they click the message
+function show_error(message) {
+    document.getElementById("error").textContent = message;
+}
+
+function show_settings() {
+    document.getElementById("settings-height").value = config.height;
+    document.getElementById("settings-width").value = config.width;
+    document.getElementById("settings-history").value = config.history === UNLIMITED_HISTORY ? "" : config.history;
+    document.getElementById("settings-swipe").value = config.swipe_threshold;
+    document.getElementById("settings-animations").value = config.animation_speed;
+    document.getElementById("settings-keys").value = config.keymap.preset;
+    document.getElementById("settings").showModal();
+}
+
+function close_settings() {
+    if (this.returnValue !== "save") {
+        return;
+    }
+    for (const field of ["height", "width"]) {
+        const size = parseInt(document.getElementById(`settings-${field}`).value);
+        if (size >= min_size() && size <= max_size()) {
+            config[field] = size;
+        }
+    }
+    // An empty history field keeps every move
+    const history_field = document.getElementById("settings-history").value;
+    const history = history_field === "" ? UNLIMITED_HISTORY : parseInt(history_field);
+    if (history === UNLIMITED_HISTORY || history >= 0) {
+        config.history = history;
+        // replay boards keep every move, to step back through the whole game,
+        // and daily challenges are played without undo by everyone
+        boards
+            .filter(board => board.replay === null && board.game.date === undefined)
+            .forEach(board => board.game.set_max_history(max_history()));
+        save_games();
+    }
+    const swipe = parseInt(document.getElementById("settings-swipe").value);
+    if (swipe > 0) {
+        config.swipe_threshold = swipe;
+    }
+    config.animation_speed = document.getElementById("settings-animations").value;
+    const preset = document.getElementById("settings-keys").value;
+    if (config.keymap.preset !== preset) {
+        config.keymap = {preset, bindings: {}};
+    }
+    apply_config(JSON.stringify(config));
+}
 
 function new_tile(i, j, value, extra_class) {
     const outer = document.createElement("div");
     outer.classList.add("tile");
     outer.classList.add(`tile-${value}`);
-    outer.classList.add(`position-${i}-${j}`);
+    set_position(outer, i, j);
     if (extra_class) {
         outer.classList.add(extra_class);
     }
@@ -17,22 +120,275 @@ function new_tile(i, j, value, extra_class) {
     return outer;
 }
 
-function initialize_grid() {
+// The position-i-j class finds the tile at a cell, --row and --col place it there
+function set_position(tile, i, j) {
+    tile.classList.add(`position-${i}-${j}`);
+    tile.style.setProperty("--row", `${i}`);
+    tile.style.setProperty("--col", `${j}`);
+}
+
+// History length to give to games, `undefined` for unlimited
+function max_history() {
+    return config.history === UNLIMITED_HISTORY ? undefined : config.history;
+}
+
+// Height or width of the configuration brought within the supported sizes, like `Config::game`
+function board_size(size) {
+    return Math.min(Math.max(size, min_size()), max_size());
+}
+
+function new_game() {
     const seed_area = document.getElementById("seed").value;
-    const {board, seed: seed_s} = JSON.parse(new_game(4, 4, 0, seed_area));
-    const seed = BigInt(seed_s);
-    let numbers = document.getElementById("numbers");
-    numbers.innerHTML = "";
-    for (let i = 0; i < board.length; i++) {
-        for (let j = 0; j < board[0].length; j++) {
-            if (board[i][j] === 0) {
-                continue;
-            }
-            const tile = new_tile(i, j, board[i][j], "tile-new");
-            numbers.appendChild(tile);
+    const seed = seed_area === "" ? undefined : BigInt(seed_area);
+    return new Game(board_size(config.height), board_size(config.width), max_history(), seed);
+}
+
+// The day is taken in UTC like in the terminal frontends,
+// so that every player gets the same challenge at the same time
+function today_game() {
+    const today = new Date();
+    return Game.daily(today.getUTCFullYear(), today.getUTCMonth() + 1, today.getUTCDate());
+}
+
+// Store the games of every board, so that they continue identically after a reload
+function save_games() {
+    const games = boards.filter(board => board.replay === null);
+    const saved = {
+        active: games.indexOf(active),
+        boards: games.map(board => ({
+            game: board.game.save(),
+            date: board.game.date,
+            record: board.game.record,
+            continued: board.continued,
+        })),
+    };
+    localStorage.setItem(GAMES, JSON.stringify(saved));
+    if (active !== null) {
+        history.replaceState(null, "", active_link());
+    }
+}
+
+function active_link() {
+    return active.replay === null ? link(active.game) : link(active.replay.source, true);
+}
+
+// URL fragment reproducing a game: the rules it follows, its seed and size, or daily challenge,
+// and its moves when they are all known. Replay links open the moves in the replay player
+function link(game, replay = false) {
+    const params = new URLSearchParams();
+    params.set("rules", `${rules()}`);
+    if (game.date !== undefined) {
+        params.set("daily", game.date);
+    } else {
+        params.set("seed", `${game.seed}`);
+        params.set("size", `${game.height}x${game.width}`);
+    }
+    if (game.record) {
+        params.set("moves", game.record);
+    }
+    if (replay) {
+        params.set("replay", "");
+    }
+    return `#${params}`;
+}
+
+// Game of a link made by `link`, and whether to replay it.
+// Throws if the link is invalid, or if its moves can't all be played
+function game_of_link(hash) {
+    const params = new URLSearchParams(hash.slice(1));
+    const link_rules = params.get("rules");
+    if (link_rules === null) {
+        throw "it doesn't say which rules it follows";
+    }
+    if (link_rules !== `${rules()}`) {
+        throw `made with rules "${link_rules}", this page plays with rules ${rules()}`;
+    }
+    let game;
+    if (params.has("daily")) {
+        const [year, month, day] = params.get("daily").split("-").map(Number);
+        game = Game.daily(year, month, day);
+        if (game === undefined) {
+            throw `invalid date ${params.get("daily")}`;
         }
+    } else {
+        const [height, width] = (params.get("size") ?? `${board_size(config.height)}x${board_size(config.width)}`).split("x").map(Number);
+        game = new Game(height, width, max_history(), BigInt(params.get("seed")));
+    }
+    try {
+        game.play(params.get("moves") ?? "");
+    } catch (error) {
+        game.free();
+        throw `its moves stop early, ${error}`;
+    }
+    return {game, replay: params.has("replay")};
+}
+
+// Open the game of the URL fragment in a new board, unless the active board already shows it
+function open_link() {
+    if (location.hash.length <= 1 || (active !== null && location.hash === active_link())) {
+        return;
+    }
+    let linked;
+    try {
+        linked = game_of_link(location.hash);
+    } catch (error) {
+        show_error(`Could not open the link: ${error}`);
+        return;
+    }
+    if (linked.replay) {
+        open_replay(linked.game);
+        linked.game.free();
+    } else {
+        open_board(linked.game);
+    }
+}
+
+// Open a board playing back the moves of `game`, which stays untouched
+function open_replay(game) {
+    const source = game.at_move(game.moves);
+    const board = new Board(source.at_move(0));
+    board.begin_replay(source);
+    boards.push(board);
+    activate(board);
+}
+
+function replay_game() {
+    open_replay(active.replay?.source ?? active.game);
+}
+
+// Bring back the boards stored by save_games. Returns false if there are none
+function restore_games() {
+    let saved;
+    let games;
+    try {
+        saved = JSON.parse(localStorage.getItem(GAMES));
+        games = saved.boards.map(({game, date, record, continued}) => ({
+            game: Game.load(game, date ?? undefined, record),
+            continued: continued === true,
+        }));
+    } catch {
+        return false;
+    }
+    if (games.length === 0) {
+        return false;
+    }
+    boards = games.map(({game, continued}) => {
+        const board = new Board(game);
+        board.continued = continued;
+        board.show_status(game.status);
+        return board;
+    });
+    activate(boards[saved.active] ?? boards[0]);
+    return true;
+}
+
+function best_score() {
+    return BigInt(localStorage.getItem(BEST_SCORE) ?? "0");
+}
+
+function show_best() {
+    document.getElementById("best").textContent = `${best_score()}`;
+}
+
+function record_best(score) {
+    if (score > best_score()) {
+        localStorage.setItem(BEST_SCORE, `${score}`);
+        show_best();
+    }
+}
+
+function initialize_grid() {
+    active.start(new_game());
+}
+
+function start_daily() {
+    active.start(today_game());
+}
+
+function add_board() {
+    open_board(new_game());
+}
+
+function open_board(game) {
+    const board = new Board(game);
+    boards.push(board);
+    activate(board);
+}
+
+function close_board(board) {
+    if (boards.length === 1) {
+        return;
+    }
+    boards = boards.filter(other => other !== board);
+    board.destroy();
+    if (active === board) {
+        activate(boards[boards.length - 1]);
+    } else {
+        update_boards();
+    }
+    save_games();
+}
+
+function activate(board) {
+    active = board;
+    update_boards();
+    show_info();
+    save_games();
+}
+
+// Highlight the active board when there are several, and let them be closed
+function update_boards() {
+    for (const board of boards) {
+        board.element.classList.toggle("active", boards.length > 1 && board === active);
+        board.element.querySelector(".close-board").hidden = boards.length === 1;
+    }
+}
+
+// Seed, score and daily challenge of the active board
+function show_info() {
+    document.getElementById("current-seed").textContent = `${active.game.seed}`;
+    show_counters();
+    show_daily_info();
+}
+
+// Score, moves and max tile of the active board
+function show_counters() {
+    document.getElementById("score").textContent = `${active.game.score}`;
+    document.getElementById("moves").textContent = `${active.game.moves}`;
+    document.getElementById("max-tile").textContent = `${active.game.max_tile}`;
+}
+
+// Float the points gained by a move of the active board above its score
+function show_score_gain(gained) {
+    const addition = document.createElement("div");
+    addition.classList.add("score-addition");
+    addition.textContent = `+${gained}`;
+    addition.onanimationend = () => addition.remove();
+    document.getElementById("score-container").appendChild(addition);
+}
+
+function daily_results() {
+    return JSON.parse(localStorage.getItem(DAILY_RESULTS) ?? "{}");
+}
+
+function record_daily(date, score) {
+    const results = daily_results();
+    if ((results[date] ?? 0) < score) {
+        results[date] = score;
+        localStorage.setItem(DAILY_RESULTS, JSON.stringify(results));
+    }
+    show_daily_info();
+}
+
+function show_daily_info() {
+    const info = document.getElementById("daily-info");
+    const date = active.game.date;
+    if (date === undefined) {
+        info.textContent = "";
+    } else {
+        const best = daily_results()[date] ?? 0;
+        info.textContent = `Daily challenge ${date}, best score: ${best}`;
     }
-    document.getElementById("current-seed").textContent = `${seed}`;
 }
 
 class ToMove {
@@ -45,68 +401,396 @@ class ToMove {
     }
 }
 
-function render(push_result) {
-    const height = push_result.transitions.length;
-    const width = push_result.transitions[0].length;
-    document.querySelectorAll(`.tile.position-new`).forEach(e => e.classList.remove("position-new"));
-    document.querySelectorAll(`.tile.tile-merged`).forEach(e => e.classList.remove("tile-merged"));
-    document.querySelectorAll(`.tile.to-remove`).forEach(e => e.remove());
-    const numbers = document.getElementById("numbers");
-    const to_move = [];
-    for (let i = 0; i < height; i++) {
-        for (let j = 0; j < width; j++) {
-            for (const [oi, oj] of push_result.transitions[i][j]) {
-                const child = document.querySelector(`.tile.position-${oi}-${oj}`);
-                to_move.push(new ToMove(child, oi, oj, i, j));
+function free_result(result, slides) {
+    slides.forEach(slide => slide.free());
+    result.free();
+}
+
+// A board of the page showing one game, created from the board template
+class Board {
+    constructor(game) {
+        this.element = document.getElementById("board-template").content.firstElementChild.cloneNode(true);
+        this.grid = this.element.querySelector(".grid-container");
+        this.numbers = this.element.querySelector(".numbers-container");
+        this.score = this.element.querySelector(".board-score");
+        this.element.onclick = () => activate(this);
+        this.element.querySelector(".close-board").onclick = e => {
+            e.stopPropagation();
+            close_board(this);
+        };
+        const container = this.element.querySelector(".game-container");
+        container.onpointerdown = e => this.swipe_start(e);
+        container.onpointerup = e => this.swipe_end(e);
+        container.onpointercancel = () => this.swipe = null;
+        this.swipe = null;
+        this.overlay = this.element.querySelector(".overlay");
+        // The win overlay was closed to keep playing
+        this.continued = false;
+        const buttons = {
+            ".continue": () => {
+                this.continued = true;
+                this.show_status(this.game.status);
+                save_games();
+            },
+            ".new-game": () => this.start(new_game()),
+            ".retry": () => this.start(this.game.restart()),
+            ".share-seed": () => copy_text(`${this.game.seed}`),
+        };
+        for (const [selector, action] of Object.entries(buttons)) {
+            this.overlay.querySelector(selector).onclick = e => {
+                e.stopPropagation();
+                activate(this);
+                action();
+            };
+        }
+        this.controls = this.element.querySelector(".replay-controls");
+        // Player of the moves of replay.source, null for boards played by hand
+        this.replay = null;
+        const steps = {
+            ".replay-back": () => this.replay_back(),
+            ".replay-forward": () => this.replay_forward(),
+        };
+        for (const [selector, step] of Object.entries(steps)) {
+            this.controls.querySelector(selector).onclick = () => {
+                this.pause();
+                step();
+            };
+        }
+        this.controls.querySelector(".replay-play").onclick = () => this.toggle_play();
+        this.controls.querySelector(".replay-seek").oninput = e => {
+            this.pause();
+            this.seek(Number(e.target.value));
+        };
+        this.controls.querySelector(".replay-speed").onchange = e => this.set_speed(Number(e.target.value));
+        document.getElementById("boards").appendChild(this.element);
+        this.game = null;
+        this.start(game);
+    }
+
+    // Play `game` by hand, ending any replay
+    start(game) {
+        this.end_replay();
+        this.set_game(game);
+    }
+
+    set_game(game) {
+        this.game?.free();
+        this.game = game;
+        this.continued = false;
+        this.show_state();
+        this.show_status(game.status);
+        save_games();
+    }
+
+    // Overlay over the board when the game is won, until the player continues, or over
+    show_status(status) {
+        const won = status === Status.Won && !this.continued && this.replay === null;
+        const over = status === Status.Over && this.replay === null;
+        this.overlay.hidden = !won && !over;
+        this.overlay.classList.toggle("won", won);
+        this.overlay.querySelector(".overlay-message").textContent = won ? "You win!" : "Game over!";
+    }
+
+    destroy() {
+        this.end_replay();
+        this.game.free();
+        this.element.remove();
+    }
+
+    begin_replay(source) {
+        this.replay = {source, timer: null, speed: Number(this.controls.querySelector(".replay-speed").value)};
+        this.controls.querySelector(".replay-seek").max = `${source.moves}`;
+        this.controls.hidden = false;
+        this.show_status(this.game.status);
+        this.update_replay();
+    }
+
+    end_replay() {
+        if (this.replay === null) {
+            return;
+        }
+        this.pause();
+        this.replay.source.free();
+        this.replay = null;
+        this.controls.hidden = true;
+    }
+
+    // Play the next recorded move. Returns false at the end of the record
+    replay_forward() {
+        const letter = this.replay.source.record[this.game.moves];
+        if (letter === undefined) {
+            return false;
+        }
+        const result = this.game.push(LETTERS[letter]);
+        if (result !== undefined) {
+            this.render(result);
+        }
+        this.update_replay();
+        return true;
+    }
+
+    replay_back() {
+        const result = this.game.undo();
+        if (result !== undefined) {
+            this.render_undo(result);
+        }
+        this.update_replay();
+    }
+
+    // Jump to the position after the first `moves` recorded moves
+    seek(moves) {
+        this.set_game(this.replay.source.at_move(moves));
+        this.update_replay();
+    }
+
+    toggle_play() {
+        if (this.replay.timer === null) {
+            this.play();
+        } else {
+            this.pause();
+        }
+    }
+
+    play() {
+        if (this.game.moves === this.replay.source.moves) {
+            this.seek(0);
+        }
+        this.replay.timer = setInterval(() => {
+            if (!this.replay_forward()) {
+                this.pause();
             }
+        }, REPLAY_STEP / this.replay.speed);
+        this.update_replay();
+    }
+
+    pause() {
+        if (this.replay === null || this.replay.timer === null) {
+            return;
         }
+        clearInterval(this.replay.timer);
+        this.replay.timer = null;
+        this.update_replay();
     }
-    for (const move of to_move) {
-        move.child.classList.remove(`position-${move.oi}-${move.oj}`);
-        move.child.classList.add(`position-${move.ni}-${move.nj}`);
+
+    set_speed(speed) {
+        const playing = this.replay.timer !== null;
+        this.pause();
+        this.replay.speed = speed;
+        if (playing) {
+            this.play();
+        }
     }
-    for (let i = 0; i < height; i++) {
-        for (let j = 0; j < width; j++) {
-            if (push_result.transitions[i][j].length === 2) {
-                const old_children = document.querySelectorAll(`.tile.position-${i}-${j}`);
-                const new_value = parseInt(old_children[0].querySelector(".tile-inner").textContent) * 2;
-                const merged = new_tile(i, j, new_value, "tile-merged");
-                numbers.appendChild(merged);
-                old_children.forEach(e => e.classList.add("to-remove"));
+
+    update_replay() {
+        const {moves} = this.game;
+        this.controls.querySelector(".replay-seek").value = `${moves}`;
+        this.controls.querySelector(".replay-position").textContent = `${moves} / ${this.replay.source.moves}`;
+        this.controls.querySelector(".replay-play").textContent = this.replay.timer === null ? "Play" : "Pause";
+    }
+
+    tile(i, j) {
+        return this.numbers.querySelector(`.tile.position-${i}-${j}`);
+    }
+
+    // Cells of an empty board, sized so that its largest side takes the same room for any size
+    build_grid() {
+        const {height, width} = this.game;
+        const cell_size = (BOARD_SIZE - GAP * (Math.max(height, width) - 1)) / Math.max(height, width);
+        const style = this.element.style;
+        style.setProperty("--rows", `${height}`);
+        style.setProperty("--columns", `${width}`);
+        style.setProperty("--cell-size", `${cell_size}px`);
+        this.grid.innerHTML = "";
+        for (let i = 0; i < height; i++) {
+            const row = document.createElement("div");
+            row.classList.add("grid-row");
+            for (let j = 0; j < width; j++) {
+                const cell = document.createElement("div");
+                cell.classList.add("empty-cell");
+                row.appendChild(cell);
             }
+            this.grid.appendChild(row);
         }
     }
-    const spawned = new_tile(
-        push_result.spawned_row,
-        push_result.spawned_col,
-        push_result.spawned_value,
-        "tile-new",
-    )
-    numbers.appendChild(spawned);
-    document.getElementById("score").textContent = `${push_result.new_score}`;
+
+    show_state() {
+        const {height, width} = this.game;
+        const board = this.game.board();
+        this.build_grid();
+        this.numbers.innerHTML = "";
+        for (let i = 0; i < height; i++) {
+            for (let j = 0; j < width; j++) {
+                const value = board[i * width + j];
+                if (value === 0n) {
+                    continue;
+                }
+                this.numbers.appendChild(new_tile(i, j, value, "tile-new"));
+            }
+        }
+        this.show_score();
+        if (this === active) {
+            show_info();
+        }
+    }
+
+    show_score() {
+        this.score.textContent = `${this.game.score}`;
+        if (this.replay === null) {
+            record_best(this.game.score);
+        }
+        if (this === active) {
+            show_counters();
+        }
+    }
+
+    // Leave the tiles of the previous move at rest
+    end_animations() {
+        this.numbers.querySelectorAll(`.tile.position-new`).forEach(e => e.classList.remove("position-new"));
+        this.numbers.querySelectorAll(`.tile.tile-merged`).forEach(e => e.classList.remove("tile-merged"));
+        this.numbers.querySelectorAll(`.tile.to-remove`).forEach(e => e.remove());
+    }
+
+    // Tiles of the slides, found before any of them moves
+    slide_tiles(slides) {
+        return slides.map(slide => new ToMove(
+            this.tile(slide.from_row, slide.from_col),
+            slide.from_row,
+            slide.from_col,
+            slide.to_row,
+            slide.to_col,
+        ));
+    }
+
+    push(direction) {
+        if (!this.overlay.hidden || this.replay !== null) {
+            return;
+        }
+        const result = this.game.push(direction);
+        if (result !== undefined) {
+            this.render(result);
+            save_games();
+        }
+    }
+
+    swipe_start(e) {
+        if (e.isPrimary) {
+            this.swipe = {x: e.clientX, y: e.clientY};
+        }
+    }
+
+    // Push along the longest axis of the swipe once it covers the threshold
+    swipe_end(e) {
+        if (!e.isPrimary || this.swipe === null) {
+            return;
+        }
+        const dx = e.clientX - this.swipe.x;
+        const dy = e.clientY - this.swipe.y;
+        this.swipe = null;
+        if (Math.max(Math.abs(dx), Math.abs(dy)) < swipe_threshold()) {
+            return;
+        }
+        activate(this);
+        if (Math.abs(dx) > Math.abs(dy)) {
+            this.push(dx > 0 ? Direction.Right : Direction.Left);
+        } else {
+            this.push(dy > 0 ? Direction.Down : Direction.Up);
+        }
+    }
+
+    undo() {
+        if (this.replay !== null) {
+            return;
+        }
+        const result = this.game.undo();
+        if (result !== undefined) {
+            this.render_undo(result);
+            save_games();
+        }
+    }
+
+    render(push_result) {
+        const slides = push_result.slides;
+        const gained = push_result.score - BigInt(this.score.textContent);
+        if (gained > 0n && this === active) {
+            show_score_gain(gained);
+        }
+        this.end_animations();
+        const to_move = this.slide_tiles(slides);
+        for (const move of to_move) {
+            move.child.classList.remove(`position-${move.oi}-${move.oj}`);
+            set_position(move.child, move.ni, move.nj);
+        }
+        const merged_cells = new Set();
+        slides.forEach((slide, index) => {
+            if (!slide.merged) {
+                return;
+            }
+            to_move[index].child.classList.add("to-remove");
+            if (!merged_cells.has(`${slide.to_row}-${slide.to_col}`)) {
+                merged_cells.add(`${slide.to_row}-${slide.to_col}`);
+                this.numbers.appendChild(new_tile(slide.to_row, slide.to_col, slide.value * 2n, "tile-merged"));
+            }
+        });
+        const spawned = new_tile(
+            push_result.spawned_row,
+            push_result.spawned_col,
+            push_result.spawned_value,
+            "tile-new",
+        )
+        this.numbers.appendChild(spawned);
+        this.show_score();
+        if (this.game.date !== undefined && this.replay === null) {
+            record_daily(this.game.date, Number(push_result.score));
+        }
+        this.show_status(push_result.status);
+        free_result(push_result, slides);
+    }
+
+    // Slide the tiles back to where they were before the undone move, splitting merged tiles
+    render_undo(undo_result) {
+        const slides = undo_result.slides;
+        this.end_animations();
+        this.tile(undo_result.spawned_row, undo_result.spawned_col).remove();
+        const to_move = this.slide_tiles(slides);
+        slides.forEach((slide, index) => {
+            if (slide.merged) {
+                to_move[index].child?.remove();
+                to_move[index].child = new_tile(slide.from_row, slide.from_col, slide.value);
+                this.numbers.appendChild(to_move[index].child);
+            }
+        });
+        // Lay the split tiles out where they start before moving them
+        void this.numbers.offsetWidth;
+        for (const move of to_move) {
+            move.child.classList.remove(`position-${move.oi}-${move.oj}`);
+            set_position(move.child, move.ni, move.nj);
+        }
+        this.show_score();
+        this.show_status(undo_result.status);
+        free_result(undo_result, slides);
+    }
+}
+
+function undo_move() {
+    active.undo();
 }
 
 function keydown_event(e) {
-    let direction = '';
-    switch (e.key) {
-        case 'ArrowUp':
-            direction = 'U';
-            break;
-        case 'ArrowDown':
-            direction = 'D';
-            break;
-        case 'ArrowLeft':
-            direction = 'L';
-            break;
-        case 'ArrowRight':
-            direction = 'R';
-            break;
+    if (e.target instanceof HTMLInputElement || document.getElementById("settings").open) {
+        return;
     }
-    if (direction !== '') {
-        let result = JSON.parse(push(direction));
-        if (result !== null) {
-            render(result);
-        }
+    const action = key_action(e.key);
+    if (action in DIRECTIONS) {
+        e.preventDefault();
+        active.push(DIRECTIONS[action]);
+    } else if (action === "undo") {
+        undo_move();
+    } else if (action === "new_game") {
+        initialize_grid();
+    } else if (action === "daily") {
+        start_daily();
+    } else if (action === "settings") {
+        show_settings();
     }
 }
 
@@ -121,14 +805,22 @@ function load_seed() {
 }
 
 function copy_seed() {
-    const seed = document.getElementById("current-seed").textContent;
-    navigator.clipboard.writeText(seed).then(_ => {
+    copy_text(document.getElementById("current-seed").textContent);
+}
+
+// Copy to the clipboard and tell it with the popup of the copy button
+function copy_text(text) {
+    navigator.clipboard.writeText(text).then(_ => {
         const popup = document.getElementById("copy-popup");
         popup.classList.toggle("show");
         popup.onanimationend = () => popup.classList.toggle("show");
     });
 }
 
+function copy_link() {
+    copy_text(location.href);
+}
+
 function clear_seed() {
     document.getElementById("seed").value = "";
 }
@@ -136,8 +828,17 @@ function clear_seed() {
 
 document.addEventListener('keydown', keydown_event);
 document.getElementById("new-game").onclick = initialize_grid;
+document.getElementById("undo").onclick = undo_move;
+document.getElementById("daily").onclick = start_daily;
+document.getElementById("add-board").onclick = add_board;
+document.getElementById("replay").onclick = replay_game;
+document.getElementById("settings-button").onclick = show_settings;
+document.getElementById("settings").onclose = close_settings;
 document.getElementById("seed").oninput = only_numbers;
 document.getElementById("load-seed").onclick = load_seed;
 document.getElementById("copy-seed").onclick = copy_seed;
 document.getElementById("clear-seed").onclick = clear_seed;
+document.getElementById("copy-link").onclick = copy_link;
+document.getElementById("error").onclick = () => show_error("");
+window.addEventListener("hashchange", open_link);
 
@@ -1,68 +1,401 @@
 use std::cell::RefCell;
 
-use backend::Game;
-use serde_json::{to_string, Number, Value};
+use backend::{
+    animation::{self, Slide},
+    config::{Config, UNLIMITED_HISTORY},
+    daily::{DailyChallenge, Date},
+    keymap::Key,
+    stats::WIN_TILE,
+    PushResult, MAX_SIZE, MIN_SIZE, RULES,
+};
+use serde_json::to_string;
 use wasm_bindgen::prelude::wasm_bindgen;
 
 thread_local! {
-static GAME: RefCell<Game> = RefCell::new(Game::default());
+static CONFIG: RefCell<Config> = RefCell::new(Config::default());
 }
 
-#[wasm_bindgen(js_name = new_game)]
-pub fn new_game(height: usize, width: usize, max_history: usize, seed: String) -> String {
-    GAME.with(|game| {
-        *game.borrow_mut() = if let Ok(seed) = seed.parse() {
-            Game::from_seed(height, width, max_history, seed)
-        } else {
-            Game::new(height, width, max_history)
+/// Smallest height and width of a board
+#[wasm_bindgen(js_name = min_size)]
+pub fn min_size() -> usize {
+    MIN_SIZE
+}
+
+/// Largest height and width of a board
+#[wasm_bindgen(js_name = max_size)]
+pub fn max_size() -> usize {
+    MAX_SIZE
+}
+
+/// Version of the rules games are played with, written in links
+#[wasm_bindgen]
+pub fn rules() -> u32 {
+    RULES
+}
+
+/// Direction of a push
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub enum Direction {
+    Up,
+    Left,
+    Down,
+    Right,
+}
+
+impl From<Direction> for backend::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => backend::Direction::U,
+            Direction::Left => backend::Direction::L,
+            Direction::Down => backend::Direction::D,
+            Direction::Right => backend::Direction::R,
+        }
+    }
+}
+
+impl Direction {
+    /// Letter of the direction in move lists
+    fn letter(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Left => 'L',
+            Direction::Down => 'D',
+            Direction::Right => 'R',
         }
-        .unwrap_or_default();
-        let mut result = Value::default();
-        result["board"] = game
-            .borrow()
-            .board()
-            .iter()
-            .map(|row| {
-                row.iter()
-                    .map(|&cell| Value::Number(Number::from(cell)))
-                    .collect::<Vec<_>>()
+    }
+}
+
+/// Whether a game goes on
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Status {
+    Playing,
+    /// [`WIN_TILE`] was reached and moves are still possible
+    Won,
+    /// No move is possible
+    Over,
+}
+
+/// A tile moving from one cell to another
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug)]
+pub struct TileSlide {
+    pub from_row: usize,
+    pub from_col: usize,
+    pub to_row: usize,
+    pub to_col: usize,
+    pub value: u64,
+    /// The tile merges with another one at the end of a push,
+    /// or comes out of a merged tile when undoing
+    pub merged: bool,
+}
+
+/// What a push or an undo changed on the board
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct MoveResult {
+    slides: Vec<TileSlide>,
+    /// Cell of the tile spawned by the push, or removed by the undo
+    pub spawned_row: usize,
+    pub spawned_col: usize,
+    pub spawned_value: u64,
+    /// Score after the move
+    pub score: u64,
+    /// Status of the game after the move
+    pub status: Status,
+}
+
+#[wasm_bindgen]
+impl MoveResult {
+    /// Tiles that changed cell, only moving ones
+    #[wasm_bindgen(getter)]
+    pub fn slides(&self) -> Vec<TileSlide> {
+        self.slides.clone()
+    }
+}
+
+impl MoveResult {
+    fn new(before: &[Vec<u64>], result: &PushResult) -> Self {
+        let merges = animation::merges(result);
+        let slides = animation::slides(before, result)
+            .into_iter()
+            .filter(|slide| slide.from != slide.to || merges.contains(&slide.to))
+            .map(|Slide { from, to, value }| TileSlide {
+                from_row: from.0,
+                from_col: from.1,
+                to_row: to.0,
+                to_col: to.1,
+                value,
+                merged: merges.contains(&to),
             })
-            .collect::<Vec<_>>()
-            .into();
-        result["seed"] = game.borrow().seed().to_string().into();
-        to_string(&result).unwrap_or_default()
-    })
+            .collect();
+        Self {
+            slides,
+            spawned_row: result.spawned_row,
+            spawned_col: result.spawned_col,
+            spawned_value: result.spawned_value,
+            score: result.new_score,
+            status: Status::Playing,
+        }
+    }
+
+    fn with_status(mut self, status: Status) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// The same move played backwards, ending with `score`
+    fn reversed(mut self, score: u64) -> Self {
+        for slide in &mut self.slides {
+            (slide.from_row, slide.to_row) = (slide.to_row, slide.from_row);
+            (slide.from_col, slide.to_col) = (slide.to_col, slide.from_col);
+        }
+        self.score = score;
+        self
+    }
 }
 
+/// A game played in the page. Each board of the page holds its own, created with
+/// `new Game(...)` or `Game.daily(...)` and destroyed with `free()`
 #[wasm_bindgen]
-pub fn push(direction: char) -> Option<String> {
-    GAME.with(|game| {
-        to_string(
-            &direction
+pub struct Game {
+    game: backend::Game,
+    /// Date of the daily challenge being played
+    date: Option<String>,
+    /// Every move played since the start, as `U`, `L`, `D` and `R`
+    record: String,
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// Start a game with a random seed if none is given, and an unlimited history
+    /// if no `max_history` is given.
+    /// Throws if the size is not between [`MIN_SIZE`] and [`MAX_SIZE`]
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        height: usize,
+        width: usize,
+        max_history: Option<usize>,
+        seed: Option<u64>,
+    ) -> Result<Game, String> {
+        let max_history = max_history.unwrap_or(UNLIMITED_HISTORY);
+        match seed {
+            Some(seed) => backend::Game::from_seed(height, width, max_history, seed),
+            None => backend::Game::new(height, width, max_history),
+        }
+        .map(|game| Game {
+            game,
+            date: None,
+            record: String::new(),
+        })
+        .ok_or_else(|| {
+            format!(
+                "height and width must be between {} and {}",
+                MIN_SIZE, MAX_SIZE
+            )
+        })
+    }
+
+    /// Start the daily challenge of the given date, as seen by the player.
+    /// Returns `undefined` if the date doesn't exist
+    pub fn daily(year: i32, month: u8, day: u8) -> Option<Game> {
+        let challenge = DailyChallenge::new(Date::new(year, month, day)?);
+        Some(Game {
+            game: challenge.game(),
+            date: Some(challenge.date.to_string()),
+            record: String::new(),
+        })
+    }
+
+    /// Date of the daily challenge, `undefined` for other games
+    #[wasm_bindgen(getter)]
+    pub fn date(&self) -> Option<String> {
+        self.date.clone()
+    }
+
+    /// Moves played since the start, as `U`, `L`, `D` and `R`
+    #[wasm_bindgen(getter)]
+    pub fn record(&self) -> String {
+        self.record.clone()
+    }
+
+    /// Play a list of moves written as [`Game::record`] gives them.
+    /// Throws at the first unknown move or move that changes nothing, keeping the moves before it
+    pub fn play(&mut self, moves: &str) -> Result<(), String> {
+        for (index, letter) in moves.chars().enumerate() {
+            let direction: backend::Direction = letter
                 .try_into()
-                .ok()
-                .and_then(|direction| game.borrow_mut().push(direction)),
+                .map_err(|_| format!("unknown move \"{}\"", letter))?;
+            if self.game.push(direction).is_none() {
+                return Err(format!("move {} changes nothing", index + 1));
+            }
+            self.record.push(letter.to_ascii_uppercase());
+        }
+        Ok(())
+    }
+
+    /// The game after the first `moves` moves of its record, with every move undoable.
+    /// Returns `undefined` if the record is shorter than that
+    pub fn at_move(&self, moves: usize) -> Option<Game> {
+        let record = self.record.get(..moves)?;
+        let mut game = self.restart();
+        game.set_max_history(None);
+        game.play(record).ok()?;
+        Some(game)
+    }
+
+    /// Full state of the game, RNG and history included, to be restored by [`Game::load`].
+    /// The date of daily challenges and the record of moves are not part of it
+    pub fn save(&self) -> String {
+        self.game.save()
+    }
+
+    /// Restore a game written by [`Game::save`], with the date of the daily challenge it was
+    /// and its record of moves. Throws if `json` is not a saved game
+    pub fn load(json: &str, date: Option<String>, record: String) -> Result<Game, String> {
+        backend::Game::load(json)
+            .map(|game| Game { game, date, record })
+            .map_err(|error| error.to_string())
+    }
+
+    /// The same game from its start: same size, seed, history length and daily challenge
+    pub fn restart(&self) -> Game {
+        Game {
+            game: backend::Game::from_seed(
+                self.game.height(),
+                self.game.width(),
+                self.game.max_history(),
+                self.game.seed(),
+            )
+            .expect("the size of a running game is valid"),
+            date: self.date.clone(),
+            record: String::new(),
+        }
+    }
+
+    /// Returns `undefined` if nothing moved
+    pub fn push(&mut self, direction: Direction) -> Option<MoveResult> {
+        let before = self.game.board().clone();
+        let result = self.game.push(direction.into())?;
+        self.record.push(direction.letter());
+        Some(MoveResult::new(&before, &result).with_status(self.status()))
+    }
+
+    /// Undo the last move. Returns `undefined` if there is nothing to undo,
+    /// otherwise the slides taking the tiles back and the spawned tile to remove
+    pub fn undo(&mut self) -> Option<MoveResult> {
+        let last = self.game.history_moves().pop()?;
+        self.game.undo();
+        self.record.pop();
+        // Replaying the move from the restored state gives the same slides and spawned tile
+        let mut replay = self.game.clone();
+        replay.set_max_history(0);
+        let result = replay.push(last.direction)?;
+        Some(
+            MoveResult::new(self.game.board(), &result)
+                .reversed(self.game.score())
+                .with_status(self.status()),
         )
-        .ok()
-    })
+    }
+
+    /// Change how many moves can be undone, every one if `max_history` is `undefined`
+    pub fn set_max_history(&mut self, max_history: Option<usize>) {
+        self.game
+            .set_max_history(max_history.unwrap_or(UNLIMITED_HISTORY))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.game.height()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.game.width()
+    }
+
+    /// Values of the cells, row after row, 0 for empty ones
+    pub fn board(&self) -> Vec<u64> {
+        self.game.board().concat()
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> u64 {
+        self.game.get(row, col)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn score(&self) -> u64 {
+        self.game.score()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> u64 {
+        self.game.seed()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn moves(&self) -> usize {
+        self.game.moves()
+    }
+
+    /// Value of the biggest tile
+    #[wasm_bindgen(getter)]
+    pub fn max_tile(&self) -> u64 {
+        self.game.max_tile()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn status(&self) -> Status {
+        if self.game.is_over() {
+            Status::Over
+        } else if self.game.max_tile() >= WIN_TILE {
+            Status::Won
+        } else {
+            Status::Playing
+        }
+    }
 }
 
-#[wasm_bindgen]
-pub fn undo() -> String {
-    GAME.with(|game| {
-        game.borrow_mut().undo();
-        to_string(game.borrow().board()).unwrap_or_default()
-    })
+/// Use the configuration stored by the page, in the JSON format of the terminal frontends.
+/// A missing configuration falls back to the defaults.
+/// Returns the complete configuration, to be stored back,
+/// or the parse error of an invalid one, leaving the current configuration in place
+#[wasm_bindgen(js_name = load_config)]
+pub fn load_config(json: Option<String>) -> Result<String, String> {
+    let config: Config = match json {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string())?,
+        None => Config::default(),
+    };
+    let json = to_string(&config).unwrap_or_default();
+    CONFIG.with(|current| *current.borrow_mut() = config);
+    Ok(json)
+}
+
+/// Distance in pixels a swipe must cover to push
+#[wasm_bindgen(js_name = swipe_threshold)]
+pub fn swipe_threshold() -> u32 {
+    CONFIG.with(|config| config.borrow().swipe_threshold)
 }
 
-#[wasm_bindgen(js_name = get_state)]
-pub fn get_state() -> String {
-    GAME.with(|game| to_string(game.borrow().board()).unwrap_or_default())
+/// Time taken by tiles to slide with the configured animation speed
+#[wasm_bindgen(js_name = slide_millis)]
+pub fn slide_millis() -> u32 {
+    CONFIG.with(|config| config.borrow().animation_speed.slide_duration().as_millis() as u32)
 }
 
-#[wasm_bindgen(js_name = get_score)]
-pub fn get_score() -> u64 {
-    GAME.with(|game| {
-        return game.borrow().score();
-    })
+/// Action bound to a key, named as in the keymap, such as `up` or `new_game`.
+/// `key` is the `key` property of a keyboard event. Letters not bound in upper case,
+/// typed with shift or caps lock, fall back to their lower case binding
+#[wasm_bindgen(js_name = key_action)]
+pub fn key_action(key: &str) -> Option<String> {
+    let action = CONFIG.with(|config| {
+        let keymap = &config.borrow().keymap;
+        let action = |key: &str| key.parse().ok().and_then(|key: Key| keymap.action(key));
+        action(key).or_else(|| action(&key.to_lowercase()))
+    })?;
+    serde_json::to_value(action)
+        .ok()?
+        .as_str()
+        .map(str::to_string)
 }